@@ -4,11 +4,12 @@ use criterion::{criterion_group, criterion_main, Criterion};
 
 extern crate wyrm;
 
-use wyrm::materials::{Lambertian, Metal, Dielectric};
+use wyrm::materials::{Material, ScatteringFunction, Lambertian, Metal, Specular, Refractive};
 use wyrm::primitives::*;
 use wyrm::hittable::hittable_list::HittableList;
 use wyrm::primitives::sphere::Sphere;
 use wyrm::camera::Camera;
+use wyrm::backgrounds::SkyGradient;
 use wyrm::utils::random_double;
 
 fn configure_criterion() -> Criterion {
@@ -17,11 +18,16 @@ fn configure_criterion() -> Criterion {
         .measurement_time(std::time::Duration::from_secs(7200)) // Tiempo de medición por muestra
 }
 
+// Material scattering only with the given lobe
+fn material(lobe: Arc<dyn ScatteringFunction>) -> Arc<Material> {
+    Arc::new(Material::new(lobe, Specular::new(), Refractive::new(1.0), None, 1.0, 0.0, 0.0, 0.0))
+}
+
 fn render_benchmark1() {
     env_logger::init();
 
     let mut world = HittableList::new();
-    let material_ground = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+    let material_ground = material(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
     world.add(Arc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, material_ground)));
 
     for i in -11..11 {
@@ -30,23 +36,23 @@ fn render_benchmark1() {
             let center = Point3::new(i as f64 + 0.9 * random_double(), 0.2, j as f64 + 0.9 * random_double());
 
             if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                let sphere_material: Arc<dyn Material>;
+                let sphere_material: Arc<Material>;
 
                 if choose_mat < 0.8 {
                     // diffuse
                     let albedo = color::random();
-                    sphere_material = Lambertian::new(albedo);
+                    sphere_material = material(Lambertian::new(albedo));
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
 
                 } else if choose_mat < 0.95 {
                     // metal
                     let albedo = color::random();
-                    sphere_material = Metal::new(albedo, 0.5);
+                    sphere_material = material(Metal::new(albedo, 0.5));
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
 
                 } else {
                     // glass
-                    sphere_material = Dielectric::new(1.5);
+                    sphere_material = material(Refractive::new(1.5));
                     world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
                 }
             }
@@ -64,11 +70,11 @@ fn render_benchmark1() {
     let focus_dist: f64 = 10.0;
 
     // Materials
-    let material_ground = Lambertian::new(Color::new(0.8, 0.8, 0.0));
-    let material_center = Lambertian::new(Color::new(0.1, 0.2, 0.5));
-    let material_left = Dielectric::new(1.50);
-    let material_bubble = Dielectric::new(1.0/1.50);
-    let material_right = Metal::new(Color::new(0.8, 0.6, 0.2), 1.0);
+    let material_ground = material(Lambertian::new(Color::new(0.8, 0.8, 0.0)));
+    let material_center = material(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+    let material_left = material(Refractive::new(1.50));
+    let material_bubble = material(Refractive::new(1.0/1.50));
+    let material_right = material(Metal::new(Color::new(0.8, 0.6, 0.2), 1.0));
 
     // World
 
@@ -78,8 +84,9 @@ fn render_benchmark1() {
     world.add(Arc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.4, material_bubble)));
     world.add(Arc::new(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, material_right)));
 
-    let cam: Camera = Camera::new(aspect_ratio, image_width, vfov, lookfrom, lookat, vup,
+    let mut cam: Camera = Camera::new(aspect_ratio, image_width, vfov, lookfrom, lookat, vup,
         defocus_angle ,focus_dist);
+    cam.set_background(Arc::new(SkyGradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0))));
 
     let _result = cam.render(&world, "benchmark1.ppm");
 }
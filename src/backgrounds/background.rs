@@ -0,0 +1,19 @@
+use crate::primitives::*;
+
+// Radiance arriving from infinitely far away along the direction of the rays
+// that escape the world without hitting anything.
+pub trait Background: Send + Sync {
+    fn value(&self, r: &Ray) -> Color;
+
+    // Importance sample an incoming direction, returning it along with its
+    // solid angle pdf. Backgrounds that cannot be sampled return None and are
+    // only found by rays escaping the world.
+    fn sample(&self) -> Option<(Vec3, f64)> {
+        None
+    }
+
+    // Solid angle pdf with which sample would have generated a direction
+    fn pdf(&self, _direction: &Vec3) -> f64 {
+        0.0
+    }
+}
@@ -0,0 +1,230 @@
+use std::f64::consts::PI;
+use crate::primitives::*;
+use crate::backgrounds::Background;
+use crate::external::image::Image;
+use crate::utils::{degrees_to_radians, random_double};
+
+// Equirectangular (latitude-longitude) HDR image lighting the scene from infinitely far away.
+// Texels are importance sampled proportionally to their luminance through a 2D CDF, a marginal
+// distribution over the rows and a conditional distribution over the texels of each row.
+pub struct EnvironmentLight {
+    image: Image,
+    intensity: f64,
+    sin_theta: f64,
+    cos_theta: f64,
+    width: usize,
+    height: usize,
+    weights: Vec<f64>,          // Sampling weight of every texel
+    total_weight: f64,
+    marginal_cdf: Vec<f64>,     // height + 1 entries
+    conditional_cdf: Vec<f64>,  // width + 1 entries per row
+}
+
+impl EnvironmentLight {
+    // Rotation is given in degrees around the vertical axis
+    pub fn new(filename: &str, intensity: f64, rotation: f64) -> Self {
+        let image: Image = Image::from_hdr_file(filename);
+        let radians = degrees_to_radians(rotation);
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+
+        // Texels near the poles cover a smaller solid angle, weight them by sin(theta)
+        let mut weights: Vec<f64> = Vec::with_capacity(width * height);
+        for j in 0..height {
+            let sin_theta = f64::sin(PI * (j as f64 + 0.5) / height as f64);
+            for i in 0..width {
+                let pixel = image.pixel_radiance(i as u32, j as u32);
+                let radiance = Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+                weights.push(f64::max(luminance(&radiance), 0.0) * sin_theta);
+            }
+        }
+
+        let mut conditional_cdf: Vec<f64> = Vec::with_capacity(height * (width + 1));
+        let mut marginal_cdf: Vec<f64> = Vec::with_capacity(height + 1);
+        marginal_cdf.push(0.0);
+
+        for j in 0..height {
+            let row = &weights[j * width..(j + 1) * width];
+            let row_weight: f64 = row.iter().sum();
+
+            let mut accumulated = 0.0;
+            conditional_cdf.push(0.0);
+            for (i, weight) in row.iter().enumerate() {
+                accumulated += weight;
+                // Rows without any energy are never picked, keep them uniform to avoid NaNs
+                conditional_cdf.push(if row_weight > 0.0 {
+                    accumulated / row_weight
+                } else {
+                    (i + 1) as f64 / width as f64
+                });
+            }
+
+            marginal_cdf.push(marginal_cdf[j] + row_weight);
+        }
+
+        let total_weight = marginal_cdf[height];
+        if total_weight > 0.0 {
+            for value in marginal_cdf.iter_mut() {
+                *value /= total_weight;
+            }
+        }
+
+        Self {
+            image,
+            intensity,
+            sin_theta: f64::sin(radians),
+            cos_theta: f64::cos(radians),
+            width,
+            height,
+            weights,
+            total_weight,
+            marginal_cdf,
+            conditional_cdf,
+        }
+    }
+
+    // Change a direction from world space to the space of the map
+    fn to_local(&self, d: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * d.x - self.sin_theta * d.z,
+            d.y,
+            self.sin_theta * d.x + self.cos_theta * d.z,
+        )
+    }
+
+    // Change a direction from the space of the map to world space
+    fn to_world(&self, d: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * d.x + self.sin_theta * d.z,
+            d.y,
+            -self.sin_theta * d.x + self.cos_theta * d.z,
+        )
+    }
+
+    // Texel a world space direction falls on, along with the sine of its polar angle
+    fn texel(&self, direction: &Vec3) -> (usize, usize, f64) {
+        let d = self.to_local(&direction.unit_vector());
+        let theta = f64::acos(Interval::new(-1.0, 1.0).clamp(d.y));
+        let phi = f64::atan2(-d.z, d.x) + PI;
+
+        let i = ((phi / (2.0 * PI)) * self.width as f64) as usize;
+        let j = ((theta / PI) * self.height as f64) as usize;
+
+        (i.min(self.width - 1), j.min(self.height - 1), f64::sin(theta))
+    }
+}
+
+impl Background for EnvironmentLight {
+    fn value(&self, r: &Ray) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let (i, j, _) = self.texel(&r.dir);
+        let pixel = self.image.pixel_radiance(i as u32, j as u32);
+
+        self.intensity * Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64)
+    }
+
+    fn sample(&self) -> Option<(Vec3, f64)> {
+        if self.total_weight <= 0.0 {
+            return None;
+        }
+
+        // Pick a row from the marginal distribution and a texel inside it from the conditional one
+        let (j, dv) = sample_cdf(&self.marginal_cdf, random_double());
+        let row_cdf = &self.conditional_cdf[j * (self.width + 1)..(j + 1) * (self.width + 1)];
+        let (i, du) = sample_cdf(row_cdf, random_double());
+
+        let u = (i as f64 + du) / self.width as f64;
+        let v = (j as f64 + dv) / self.height as f64;
+
+        let theta = v * PI;
+        let phi = u * 2.0 * PI;
+        let sin_theta = f64::sin(theta);
+        if sin_theta <= 0.0 {
+            return None;
+        }
+
+        let local = Vec3::new(-sin_theta * f64::cos(phi), f64::cos(theta), sin_theta * f64::sin(phi));
+        let direction = self.to_world(&local);
+
+        // Convert the density over the image into a density over solid angle
+        let texel_probability = self.weights[j * self.width + i] / self.total_weight;
+        let pdf = texel_probability * (self.width * self.height) as f64 / (2.0 * PI * PI * sin_theta);
+
+        Some((direction, pdf))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        if self.total_weight <= 0.0 {
+            return 0.0;
+        }
+
+        let (i, j, sin_theta) = self.texel(direction);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        let texel_probability = self.weights[j * self.width + i] / self.total_weight;
+        texel_probability * (self.width * self.height) as f64 / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+// Sample a normalized piecewise constant CDF, returning the chosen segment and
+// the offset inside it in [0, 1).
+fn sample_cdf(cdf: &[f64], xi: f64) -> (usize, f64) {
+    let segments = cdf.len() - 1;
+    let index = cdf.partition_point(|&c| c <= xi).clamp(1, segments) - 1;
+
+    let width = cdf[index + 1] - cdf[index];
+    let offset = if width > 0.0 {
+        Interval::new(0.0, 1.0).clamp((xi - cdf[index]) / width)
+    } else {
+        0.0
+    };
+
+    (index, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A checker has texels of very different weights, the image is looked up in images/
+    fn checker(rotation: f64) -> EnvironmentLight {
+        EnvironmentLight::new("checkerSmall.png", 1.0, rotation)
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_over_the_sphere() {
+        let light = checker(0.0);
+        assert!(light.total_weight > 0.0);
+
+        // Two samples per texel along both angles
+        let (steps_theta, steps_phi) = (2 * light.height, 2 * light.width);
+        let dtheta = PI / steps_theta as f64;
+        let dphi = 2.0 * PI / steps_phi as f64;
+
+        let mut total = 0.0;
+        for j in 0..steps_theta {
+            let theta = (j as f64 + 0.5) * dtheta;
+            for i in 0..steps_phi {
+                let phi = (i as f64 + 0.5) * dphi;
+                let direction = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                total += light.pdf(&direction) * theta.sin() * dtheta * dphi;
+            }
+        }
+        assert!((total - 1.0).abs() < 1e-3, "{total}");
+    }
+
+    #[test]
+    fn samples_have_the_pdf_of_their_direction() {
+        let light = checker(30.0);
+        for _ in 0..1000 {
+            let (direction, pdf) = light.sample().unwrap();
+            let expected = light.pdf(&direction);
+            assert!((pdf - expected).abs() <= 1e-6 * expected, "{pdf} != {expected}");
+        }
+    }
+}
@@ -0,0 +1,14 @@
+pub mod background;
+pub use self::background::Background;
+
+pub mod solid_background;
+pub use self::solid_background::SolidBackground;
+
+pub mod sky_gradient;
+pub use self::sky_gradient::SkyGradient;
+
+pub mod texture_background;
+pub use self::texture_background::TextureBackground;
+
+pub mod environment_light;
+pub use self::environment_light::EnvironmentLight;
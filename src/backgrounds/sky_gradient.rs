@@ -0,0 +1,34 @@
+use crate::primitives::*;
+use crate::backgrounds::Background;
+
+// Vertical gradient that blends linearly from the horizon color (looking down)
+// to the zenith color (looking up).
+pub struct SkyGradient {
+    horizon: Color,
+    zenith: Color,
+}
+
+impl SkyGradient {
+    pub fn new(horizon: Color, zenith: Color) -> Self {
+        Self { horizon, zenith }
+    }
+}
+
+impl Background for SkyGradient {
+    fn value(&self, r: &Ray) -> Color {
+        let unit_direction: Vec3 = r.dir.unit_vector();
+        let a: f64 = 0.5 * (unit_direction.y + 1.0);
+
+        (1.0 - a) * self.horizon + a * self.zenith
+    }
+}
+
+// The classic blue-white sky
+impl Default for SkyGradient {
+    fn default() -> Self {
+        Self {
+            horizon: Color::new(1.0, 1.0, 1.0),
+            zenith: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::primitives::*;
+use crate::backgrounds::Background;
+
+// Constant color in every direction
+pub struct SolidBackground {
+    color: Color,
+}
+
+impl SolidBackground {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for SolidBackground {
+    fn value(&self, _r: &Ray) -> Color {
+        self.color
+    }
+}
+
+impl Default for SolidBackground {
+    fn default() -> Self {
+        Self { color: Color::new(0.0, 0.0, 0.0) }
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::backgrounds::Background;
+use crate::textures::Texture;
+
+// Any texture wrapped around the scene, looked up by direction using the same
+// spherical (u, v) mapping as the Sphere primitive.
+pub struct TextureBackground {
+    texture: Arc<dyn Texture>,
+    intensity: f64,
+}
+
+impl TextureBackground {
+    pub fn new(texture: Arc<dyn Texture>, intensity: f64) -> Self {
+        Self { texture, intensity }
+    }
+}
+
+impl Background for TextureBackground {
+    fn value(&self, r: &Ray) -> Color {
+        let unit_direction: Vec3 = r.dir.unit_vector();
+        let (u, v) = Sphere::get_uv(&unit_direction);
+
+        self.intensity * self.texture.value(u, v, &unit_direction)
+    }
+}
@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use crate::hittable::{Hittable, HitRecord};
+use crate::hittable::{Hittable, HitRecord, HittableList};
 use crate::primitives::*;
 use crate::bvh::AABBox;
 use std::cmp::Ordering;
@@ -85,6 +85,12 @@ impl Hittable for BVHNode {
             rec = Some(r);
         }
 
+        // Leaves holding a single object point to it from both sides, and media would sample
+        // a second collision
+        if Arc::ptr_eq(&self.left, &self.right) {
+            return rec;
+        }
+
         // If the ray hits right node
         if let Some(r) = self.right.hit(r, ray_t) {
             rec = Some(r);
@@ -96,6 +102,54 @@ impl Hittable for BVHNode {
     fn bounding_box(&self) -> AABBox {
         self.bbox
     }
+
+    // Visits follow hit, the right child is searched up to the closest hit in the left one
+    fn bvh_visits(&self, r: &Ray, ray_t: &Interval) -> usize {
+        if !self.bbox.hit(r, &mut Interval::new(ray_t.min, ray_t.max)) {
+            return 1;
+        }
+
+        let mut visits = 1 + self.left.bvh_visits(r, ray_t);
+        if Arc::ptr_eq(&self.left, &self.right) {
+            return visits;
+        }
+
+        let mut right_t = Interval::new(ray_t.min, ray_t.max);
+        if let Some(rec) = self.left.hit(r, &mut Interval::new(ray_t.min, ray_t.max)) {
+            right_t.max = rec.t;
+        }
+        visits += self.right.bvh_visits(r, &right_t);
+        visits
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> Color {
+        if !self.bbox.hit(r, &mut Interval::new(ray_t.min, ray_t.max)) {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+
+        let left = self.left.transmittance(r, ray_t);
+        if Arc::ptr_eq(&self.left, &self.right) || (left.x <= 0.0 && left.y <= 0.0 && left.z <= 0.0) {
+            return left;
+        }
+        left * self.right.transmittance(r, ray_t)
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        // Leaves holding a single object point to it from both sides
+        let children = if Arc::ptr_eq(&self.left, &self.right) {
+            vec![&self.left]
+        } else {
+            vec![&self.left, &self.right]
+        };
+
+        for child in children {
+            if child.is_emissive() {
+                lights.add(child.clone());
+            } else {
+                child.collect_lights(lights);
+            }
+        }
+    }
 }
 
 fn box_compare(a: &Arc<dyn Hittable + Send + Sync>, b: &Arc<dyn Hittable + Send + Sync>, axis_index: i32) -> Ordering {
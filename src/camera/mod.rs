@@ -1,15 +1,20 @@
 use std::cmp::max;
 use std::fs::File;
 use std::io::{self, Write, stdout};
+use std::sync::Arc;
 use rayon::prelude::*;
 use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::primitives::vec3::{Point3, Vec3};
 use crate::primitives::ray::Ray;
 use crate::primitives::color::{Color, write_color};
-use crate::primitives::interval::Interval;
-use crate::hittable::{HitRecord, Hittable};
-use crate::utils::{degrees_to_radians, random_double, random_double_range, INFINITY};
+use crate::hittable::Hittable;
+use crate::backgrounds::{Background, SolidBackground};
+use crate::integrators::{Integrator, PathTracer, DebugIntegrator, DebugView};
+use crate::scene::Scene;
+use crate::lights::Light;
+use crate::spectrum::Wavelengths;
+use crate::utils::{degrees_to_radians, random_double};
 use crate::vec3::*;
 
 
@@ -24,11 +29,15 @@ pub struct Camera {
 
     samples_per_pixel: i32,
     pixel_samples_scale: f64,
-    depth: i32,
 
     defocus_u: Vec3,
     defocus_v: Vec3,
     defocus_angle: f64,
+
+    background: Arc<dyn Background>,
+    integrator: Arc<dyn Integrator>,
+    spectral: bool,
+    lights: Vec<Arc<dyn Light>>,
 }
 
 impl Camera {
@@ -87,8 +96,17 @@ impl Camera {
         let samples_per_pixel: i32 = 100;
         let pixel_samples_scale: f64 = 1.0 / samples_per_pixel as f64;
 
-        // How many bounces is a given ray allowd to do
-        let depth: i32 = 8;
+        // Radiance of the rays that escape the world, black unless set otherwise
+        let background: Arc<dyn Background> = Arc::new(SolidBackground::default());
+
+        // Light transport algorithm used to shade the camera rays
+        let integrator: Arc<dyn Integrator> = Arc::new(PathTracer::default());
+
+        // Colors are transported as RGB unless spectral rendering is enabled
+        let spectral = false;
+
+        // Lights besides the emissive primitives of the world
+        let lights: Vec<Arc<dyn Light>> = Vec::new();
 
         Self {
             image_width,
@@ -99,15 +117,47 @@ impl Camera {
             pixel_delta_v,
             samples_per_pixel,
             pixel_samples_scale,
-            depth,
             defocus_u,
             defocus_v,
-            defocus_angle
+            defocus_angle,
+            background,
+            integrator,
+            spectral,
+            lights,
         }
     }
 
+    pub fn set_background(&mut self, background: Arc<dyn Background>) {
+        self.background = background;
+    }
+
+    pub fn set_integrator(&mut self, integrator: Arc<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    // Render a debug view of the first hits instead of the light, replacing the integrator
+    pub fn set_debug_view(&mut self, view: DebugView) {
+        self.integrator = Arc::new(DebugIntegrator::new(view));
+    }
+
+    // Trace every sample at its own random wavelengths, so dispersive materials split light into its colors
+    pub fn set_spectral(&mut self, spectral: bool) {
+        self.spectral = spectral;
+    }
+
+    // Add a point, spot or directional light to the scene. Area lights are emissive primitives of the world.
+    pub fn add_light(&mut self, light: Arc<dyn Light>) {
+        self.lights.push(light);
+    }
+
 
     pub fn render(&self, world: &dyn Hittable, filename: &str) -> io::Result<()> {
+        self.render_pass(world, self.integrator.as_ref(), filename)
+    }
+
+    // Render the same view with another integrator, such as ambient occlusion or a debug view,
+    // into a separate image
+    pub fn render_pass(&self, world: &dyn Hittable, integrator: &dyn Integrator, filename: &str) -> io::Result<()> {
         let mut file = File::create(filename)?;
         writeln!(file, "P3\n{} {}\n255", self.image_width, self.image_height)?;
 
@@ -116,6 +166,10 @@ impl Camera {
             .template("{msg} [{elapsed_precise}] [{wide_bar:.cyan}] {pos}/{len} ({eta})")
             .progress_chars("=> "));
 
+        let mut scene = Scene::new(world, &self.lights, self.background.clone());
+        scene.spectral = self.spectral;
+        integrator.preprocess(&scene);
+
         let pixels: Vec<Vec<(i32, Color)>> = (0..self.image_height)
             .into_par_iter()
             .map(|j| {
@@ -123,8 +177,11 @@ impl Camera {
                 for i in 0..self.image_width {
                     let mut pixel_color: Vec3 = Vec3::new(0.0, 0.0, 0.0);
                     for _sample in 0..self.samples_per_pixel {
-                        let r: Ray = self.get_ray(i, j);
-                        pixel_color = pixel_color + ray_color(&r, world, self.depth);
+                        let mut r: Ray = self.get_ray(i, j);
+                        if self.spectral {
+                            r.wavelengths = Wavelengths::sample();
+                        }
+                        pixel_color = pixel_color + integrator.ray_color(&r, &scene);
                     }
                     let final_color = self.pixel_samples_scale * pixel_color;
                     row_pixels.push((i, final_color));
@@ -175,61 +232,3 @@ impl Camera {
 fn sample_square() -> Vec3 {
     Vec3::new(random_double() - 0.5, random_double() - 0.5, 0.0)
 }
-
-
-pub fn ray_color(r: &Ray, world: &dyn Hittable, depth: i32) -> Color {
-    if depth <= 0 {
-        // If we have exceeded the ray bounce limit, no more light is scattered.
-        return Color::new(0.0, 0.0, 0.0);
-    }
-
-    let hit_record: Option<HitRecord> = world.hit(r, &mut Interval::new(0.001, INFINITY));
-
-    if hit_record.is_none() {
-        return Color::new(0.0, 0.0, 0.0); // Background color
-    }
-
-    let hit_record: HitRecord = hit_record.unwrap();
-
-    let random_behavior: f64 = random_double_range(0.0, 1.0);
-
-    let mut scattered_ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
-    let mut attenuation = Color::new(0.0, 0.0, 0.0);
-
-    let material = &hit_record.mat;
-
-    let scattered: Color = if random_behavior < material.kd {
-        // Diffuse reflection
-        if material.diffuse.scatter(r, &hit_record, &mut attenuation, &mut scattered_ray) {
-            attenuation * ray_color(&scattered_ray, world, depth - 1)
-        } else {
-            Color::new(0.0, 0.0, 0.0)
-        }
-    } else if random_behavior < material.kd + material.ks {
-        // Specular reflection
-        if material.specular.scatter(r, &hit_record, &mut attenuation, &mut scattered_ray) {
-            attenuation * ray_color(&scattered_ray, world, depth - 1)
-        } else {
-            Color::new(0.0, 0.0, 0.0)
-        }
-    } else if random_behavior < material.kd + material.ks + material.kt {
-        // Refraction
-        if material.refractive.scatter(r, &hit_record, &mut attenuation, &mut scattered_ray) {
-            attenuation * ray_color(&scattered_ray, world, depth - 1)
-        } else {
-            Color::new(0.0, 0.0, 0.0)
-        }
-    } else {
-        // Absorption or no scattering
-        Color::new(0.0, 0.0, 0.0)
-    };
-
-    // Add emission if any
-    if let Some(emit_color) = material.emit {
-        scattered + emit_color
-    } else {
-        scattered
-    }
-}
-
-
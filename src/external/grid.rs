@@ -0,0 +1,80 @@
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read};
+use crate::media::VoxelGrid;
+
+// Load a voxel grid from a raw binary file: the resolution as three little endian u32 (x, y, z)
+// followed by one little endian f32 per voxel, with x varying fastest, then y, then z
+pub fn load_grid(file_path: &str, sparse: bool) -> io::Result<VoxelGrid> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(file_path)?).read_to_end(&mut bytes)?;
+
+    if bytes.len() < 12 {
+        return Err(io::Error::new(ErrorKind::UnexpectedEof, "grid file too short for its header"));
+    }
+    let header: Vec<usize> = bytes[..12]
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .collect();
+    let (nx, ny, nz) = (header[0], header[1], header[2]);
+
+    let voxels = nx.checked_mul(ny).and_then(|n| n.checked_mul(nz));
+    let values: Vec<f32> = bytes[12..]
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    if voxels != Some(values.len()) || (bytes.len() - 12) % 4 != 0 {
+        let message = format!("grid file holds {} bytes of voxels, expected {}x{}x{} f32", bytes.len() - 12, nx, ny, nz);
+        return Err(io::Error::new(ErrorKind::InvalidData, message));
+    }
+
+    Ok(if sparse {
+        VoxelGrid::new_sparse(nx, ny, nz, values)
+    } else {
+        VoxelGrid::new(nx, ny, nz, values)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // Grid file of the given resolution and values in the temporary directory
+    fn write_grid(name: &str, resolution: [u32; 3], values: &[f32]) -> PathBuf {
+        let mut bytes: Vec<u8> = resolution.iter().flat_map(|n| n.to_le_bytes()).collect();
+        bytes.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+        let path = std::env::temp_dir().join(format!("wyrm_{}_{}.grid", name, std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_the_voxels_in_order() {
+        let values: Vec<f32> = (0..24).map(|i| i as f32).collect();
+        let path = write_grid("ordered", [2, 3, 4], &values);
+        for sparse in [false, true] {
+            let grid = load_grid(path.to_str().unwrap(), sparse).unwrap();
+            assert_eq!(grid.resolution(), (2, 3, 4));
+            assert_eq!(grid.voxel(1, 2, 3), 23.0);
+            assert_eq!(grid.voxel(1, 0, 2), 13.0);
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn broken_files_are_errors() {
+        assert!(load_grid("no/such/file.grid", false).is_err());
+
+        let truncated = write_grid("truncated", [4, 4, 4], &[1.0; 63]);
+        let error = load_grid(truncated.to_str().unwrap(), false).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        fs::remove_file(truncated).unwrap();
+
+        let header = write_grid("header", [4, 4, 4], &[]);
+        fs::write(&header, [0u8; 7]).unwrap();
+        let error = load_grid(header.to_str().unwrap(), true).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        fs::remove_file(header).unwrap();
+    }
+}
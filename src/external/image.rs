@@ -8,6 +8,7 @@ pub struct Image {
     width: u32,
     height: u32,
     bdata: Option<Vec<u8>>,
+    fdata: Option<Vec<f32>>,  // Linear radiance, only kept for HDR lookups
     bytes_per_pixel: u32,
     bytes_per_scanline: u32,
 }
@@ -18,30 +19,41 @@ impl Image {
             width: 0,
             height: 0,
             bdata: None,
+            fdata: None,
             bytes_per_pixel: 3,
             bytes_per_scanline: 0,
         }
     }
 
     pub fn from_file(image_filename: &str) -> Self {
+        Image::locate(image_filename, false)
+    }
+
+    // Same as from_file but also keeps the pixels as linear floating point radiance,
+    // so HDR formats (.hdr, .exr) are not clamped to [0, 1].
+    pub fn from_hdr_file(image_filename: &str) -> Self {
+        Image::locate(image_filename, true)
+    }
+
+    fn locate(image_filename: &str, hdr: bool) -> Self {
         let mut img = Image::new();
         let filename = String::from(image_filename);
         let imagedir = env::var("RTW_IMAGES").ok();
 
         if let Some(dir) = imagedir {
-            if img.load(&format!("{}/{}", dir, image_filename)) {
+            if img.load(&format!("{}/{}", dir, image_filename), hdr) {
                 return img;
             }
         }
 
-        if img.load(&filename) ||
-           img.load(&format!("images/{}", filename)) ||
-           img.load(&format!("../images/{}", filename)) ||
-           img.load(&format!("../../images/{}", filename)) ||
-           img.load(&format!("../../../images/{}", filename)) ||
-           img.load(&format!("../../../../images/{}", filename)) ||
-           img.load(&format!("../../../../../images/{}", filename)) ||
-           img.load(&format!("../../../../../../images/{}", filename)) {
+        if img.load(&filename, hdr) ||
+           img.load(&format!("images/{}", filename), hdr) ||
+           img.load(&format!("../images/{}", filename), hdr) ||
+           img.load(&format!("../../images/{}", filename), hdr) ||
+           img.load(&format!("../../../images/{}", filename), hdr) ||
+           img.load(&format!("../../../../images/{}", filename), hdr) ||
+           img.load(&format!("../../../../../images/{}", filename), hdr) ||
+           img.load(&format!("../../../../../../images/{}", filename), hdr) {
             return img;
         }
 
@@ -49,7 +61,7 @@ impl Image {
         img
     }
 
-    fn load(&mut self, filename: &str) -> bool {
+    fn load(&mut self, filename: &str, hdr: bool) -> bool {
         let img = image::open(&Path::new(filename));
         match img {
             Ok(img) => {
@@ -57,12 +69,25 @@ impl Image {
                 self.height = img.height();
                 self.bytes_per_scanline = self.width * self.bytes_per_pixel;
                 self.bdata = Some(img.to_rgb8().into_raw());
+                if hdr {
+                    self.fdata = Some(Image::linear_data(&img));
+                }
                 true
             }
             Err(_) => false,
         }
     }
 
+    // Floating point formats are already linear, 8 and 16 bit ones are gamma encoded
+    // so they are brought back to linear with the same gamma 2 used when writing the output.
+    fn linear_data(img: &image::DynamicImage) -> Vec<f32> {
+        let data = img.to_rgb32f().into_raw();
+        match img.color() {
+            image::ColorType::Rgb32F | image::ColorType::Rgba32F => data,
+            _ => data.into_iter().map(|c| c * c).collect(),
+        }
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -83,6 +108,19 @@ impl Image {
         &self.bdata.as_ref().unwrap()[index..index + 3]
     }
 
+    // Linear radiance of a pixel, only available for images loaded with from_hdr_file
+    pub fn pixel_radiance(&self, x: u32, y: u32) -> &[f32] {
+        static MAGENTA: [f32; 3] = [1.0, 0.0, 1.0];
+        if self.fdata.is_none() {
+            return &MAGENTA;
+        }
+
+        let x = Self::clamp(x, 0, self.width);
+        let y = Self::clamp(y, 0, self.height);
+        let index = (y * self.bytes_per_scanline + x * self.bytes_per_pixel) as usize;
+        &self.fdata.as_ref().unwrap()[index..index + 3]
+    }
+
     fn clamp(x: u32, low: u32, high: u32) -> u32 {
         if x < low {
             low
@@ -2,4 +2,7 @@ pub mod image;
 pub use self::image::Image;
 
 pub mod ply;
-pub use self::ply::load_ply;
\ No newline at end of file
+pub use self::ply::load_ply;
+
+pub mod grid;
+pub use self::grid::load_grid;
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::primitives::*;
 use crate::materials::Material;
 use crate::bvh::AABBox;
+use crate::hittable::HittableList;
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Vec3,
@@ -43,10 +44,69 @@ impl HitRecord {
             -outward_normal
         };
     }
+
+    // Copy of the record as reached at t = 1 by another ray, its normal facing the ray's origin
+    pub fn reached_by(&self, ray: &Ray) -> HitRecord {
+        let outward_normal = if self.front_face { self.normal } else { -self.normal };
+        let mut rec = self.clone();
+        rec.t = 1.0;
+        rec.set_face_normal(ray, outward_normal);
+        rec
+    }
+
+    // Scattering events inside participating media have no surface, and so no normal. Neither
+    // have the points of point and spot lights.
+    pub fn in_medium(&self) -> bool {
+        self.normal.length_squared() == 0.0
+    }
 }
 
 pub trait Hittable: Sync {
     fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord>;
 
     fn bounding_box(&self) -> AABBox;
+
+    // Primitives with an emissive material report themselves so they can be sampled as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // Solid angle pdf of choosing a direction from origin towards the object with random
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    // Random direction from origin towards a point of the object
+    fn random(&self, _origin: &Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+
+    // Surface area of the object
+    fn area(&self) -> f64 {
+        0.0
+    }
+
+    // Point uniformly distributed over the surface of the object, with its outward normal
+    fn random_point(&self) -> Option<HitRecord> {
+        None
+    }
+
+    // Gather every emissive primitive inside the object into the lights list
+    fn collect_lights(&self, _lights: &mut HittableList) {}
+
+    // Nodes of bounding volume hierarchies visited looking for the closest hit of the ray, for
+    // the heatmap debug view
+    fn bvh_visits(&self, _r: &Ray, _ray_t: &Interval) -> usize {
+        0
+    }
+
+    // Fraction of the light travelling along the ray within ray_t that gets through the object.
+    // Surfaces block it entirely, media let through their transmittance.
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> Color {
+        if self.hit(r, &mut Interval::new(ray_t.min, ray_t.max)).is_some() {
+            Color::new(0.0, 0.0, 0.0)
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
 }
\ No newline at end of file
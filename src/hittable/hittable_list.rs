@@ -2,6 +2,7 @@ use std::sync::Arc;
 use crate::hittable::{Hittable, HitRecord};
 use crate::primitives::*;
 use crate::bvh::AABBox;
+use crate::utils::random_integer_range;
 
 pub struct HittableList {
     pub objects: Vec<Arc<dyn Hittable + Send + Sync>>,
@@ -41,4 +42,52 @@ impl Hittable for HittableList {
     fn bounding_box(&self) -> AABBox {
         self.bbox
     }
+
+    // Sampling a list picks one of its objects uniformly, so the pdf is the average of theirs
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let weight = 1.0 / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|object| weight * object.pdf_value(origin, direction))
+            .sum()
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let index = random_integer_range(0, self.objects.len() as i32) as usize;
+        self.objects[index].random(origin)
+    }
+
+    fn bvh_visits(&self, r: &Ray, ray_t: &Interval) -> usize {
+        let mut closest_so_far = ray_t.max;
+        let mut visits = 0;
+        for object in &self.objects {
+            visits += object.bvh_visits(r, &Interval::new(ray_t.min, closest_so_far));
+            if let Some(rec) = object.hit(r, &mut Interval::new(ray_t.min, closest_so_far)) {
+                closest_so_far = rec.t;
+            }
+        }
+        visits
+    }
+
+    // Light gets through the list only if it gets through every object, stop at the first one blocking it
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> Color {
+        let mut transmittance = Color::new(1.0, 1.0, 1.0);
+        for object in &self.objects {
+            transmittance = transmittance * object.transmittance(r, ray_t);
+            if transmittance.x <= 0.0 && transmittance.y <= 0.0 && transmittance.z <= 0.0 {
+                return Color::new(0.0, 0.0, 0.0);
+            }
+        }
+        transmittance
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        for object in &self.objects {
+            if object.is_emissive() {
+                lights.add(object.clone());
+            } else {
+                object.collect_lights(lights);
+            }
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use crate::hittable::{Hittable, HitRecord};
+use crate::hittable::{Hittable, HitRecord, HittableList};
 use crate::primitives::*;
 use crate::bvh::AABBox;
 use crate::utils::{degrees_to_radians, INFINITY, NEG_INFINITY};
@@ -48,6 +48,24 @@ impl RotationY {
 
         Self {object, sin_theta, cos_theta, bbox}
     }
+
+    // Rotate a vector from world space to object space
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x - self.sin_theta * v.z,
+            v.y,
+            self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
+
+    // Rotate a vector from object space to world space
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x + self.sin_theta * v.z,
+            v.y,
+            -self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
 }
 
 impl Hittable for RotationY {
@@ -87,4 +105,48 @@ impl Hittable for RotationY {
     fn bounding_box(&self) -> AABBox {
         self.bbox
     }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(&self.to_object(origin), &self.to_object(direction))
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.to_world(&self.object.random(&self.to_object(origin)))
+    }
+
+    fn area(&self) -> f64 {
+        self.object.area()
+    }
+
+    fn random_point(&self) -> Option<HitRecord> {
+        let mut rec = self.object.random_point()?;
+        rec.p = self.to_world(&rec.p);
+        rec.normal = self.to_world(&rec.normal);
+        Some(rec)
+    }
+
+    fn bvh_visits(&self, r: &Ray, ray_t: &Interval) -> usize {
+        let rotated_r = Ray::new(self.to_object(&r.origin()), self.to_object(&r.direction()));
+        self.object.bvh_visits(&rotated_r, ray_t)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> Color {
+        let rotated_r = Ray::new(self.to_object(&r.origin()), self.to_object(&r.direction()));
+        self.object.transmittance(&rotated_r, ray_t)
+    }
+
+    // The lights inside are rotated along with the object
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut object_lights = HittableList::new();
+        if self.object.is_emissive() {
+            object_lights.add(self.object.clone());
+        } else {
+            self.object.collect_lights(&mut object_lights);
+        }
+
+        let angle = f64::atan2(self.sin_theta, self.cos_theta).to_degrees();
+        for light in object_lights.objects {
+            lights.add(Arc::new(RotationY::new(light, angle)));
+        }
+    }
 }
\ No newline at end of file
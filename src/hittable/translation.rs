@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use crate::hittable::{Hittable, HitRecord};
+use crate::hittable::{Hittable, HitRecord, HittableList};
 use crate::primitives::*;
 use crate::bvh::AABBox;
 
@@ -34,4 +34,44 @@ impl Hittable for Translation {
     fn bounding_box(&self) -> AABBox {
         self.bbox
     }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(&(*origin - self.offset), direction)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.object.random(&(*origin - self.offset))
+    }
+
+    fn area(&self) -> f64 {
+        self.object.area()
+    }
+
+    fn random_point(&self) -> Option<HitRecord> {
+        let mut rec = self.object.random_point()?;
+        rec.p = rec.p + self.offset;
+        Some(rec)
+    }
+
+    fn bvh_visits(&self, r: &Ray, ray_t: &Interval) -> usize {
+        self.object.bvh_visits(&Ray::new(r.origin() - self.offset, r.direction()), ray_t)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> Color {
+        self.object.transmittance(&Ray::new(r.origin() - self.offset, r.direction()), ray_t)
+    }
+
+    // The lights inside are moved along with the object
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut object_lights = HittableList::new();
+        if self.object.is_emissive() {
+            object_lights.add(self.object.clone());
+        } else {
+            self.object.collect_lights(&mut object_lights);
+        }
+
+        for light in object_lights.objects {
+            lights.add(Arc::new(Translation::new(light, self.offset)));
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,52 @@
+use crate::primitives::*;
+use crate::hittable::{Hittable, HitRecord};
+use crate::integrators::Integrator;
+use crate::scene::Scene;
+use crate::utils::INFINITY;
+
+// Clay render of the first hits: each one is shaded by the fraction of cosine distributed rays
+// leaving it that travel max_distance without hitting anything. Rays escaping the world are white.
+pub struct AmbientOcclusion {
+    samples: usize,
+    max_distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, max_distance: f64) -> Self {
+        Self { samples: usize::max(samples, 1), max_distance }
+    }
+
+    // Fraction of the hemisphere around the shading normal that is open, from zero when fully
+    // occluded to one
+    pub fn visibility(&self, world: &dyn Hittable, rec: &HitRecord) -> f64 {
+        let mut open = 0;
+        for _ in 0..self.samples {
+            let mut direction = rec.normal + Vec3::random_unit_vector();
+            if direction.near_zero() {
+                direction = rec.normal;
+            }
+
+            let ray = Ray::new(rec.p, direction.unit_vector());
+            if world.hit(&ray, &mut Interval::new(0.001, self.max_distance)).is_none() {
+                open += 1;
+            }
+        }
+        open as f64 / self.samples as f64
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self::new(16, INFINITY)
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn ray_color(&self, r: &Ray, scene: &Scene) -> Color {
+        let visibility = match scene.world.hit(r, &mut Interval::new(0.001, INFINITY)) {
+            Some(rec) => self.visibility(scene.world, &rec),
+            None => 1.0,
+        };
+        Color::new(visibility, visibility, visibility)
+    }
+}
@@ -0,0 +1,436 @@
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::{LayeredBsdf, ScatteringFunction};
+use crate::integrators::{Integrator, MisHeuristic, sample_light, sample_background};
+use crate::lights::Light;
+use crate::scene::Scene;
+use crate::spectrum::Wavelengths;
+use crate::utils::INFINITY;
+
+// Bidirectional path tracer. For every camera ray a camera subpath and a light subpath starting
+// on one of the lights are traced, and every pair of their prefixes is connected with a shadow
+// ray. All the strategies able to build the same path are weighted with multiple importance
+// sampling, so light reaching the camera after bouncing close to the lights (lamps behind shades,
+// light through glass) is found from the light side instead of by chance.
+//
+// Strategies that connect light subpaths straight to the camera (light tracing) are not used, as
+// they contribute to other pixels than the one being rendered. The background and directional
+// lights, infinitely far away, are not part of the light subpaths: they are gathered from the
+// camera subpath as the path tracer does.
+pub struct BidirectionalPathTracer {
+    max_depth: usize,
+    heuristic: MisHeuristic,
+}
+
+// Quantity carried by a subpath: radiance gathered from the camera or light leaving the lights
+#[derive(Clone, Copy, PartialEq)]
+enum Transport {
+    Radiance,
+    Importance,
+}
+
+// Vertex of a camera or light subpath
+#[derive(Clone)]
+struct Vertex {
+    rec: HitRecord,
+    r_in: Ray,                                  // Ray that reached the vertex
+    bsdf: Option<Arc<LayeredBsdf>>,             // Scattering of the surface, None for the points of the lights
+    beta: Color,                                // Throughput of the subpath up to the vertex
+    pdf_fwd: f64,   // Area pdf of the vertex being sampled by its own subpath
+    pdf_rev: f64,   // Area pdf of the vertex being sampled by the opposite subpath
+    delta: bool,    // The subpath went on through a specular lobe
+    delta_rev: bool,  // Light coming back along the subpath went on through a specular lobe
+    light: Option<Arc<dyn Light>>,              // Light the vertex lies on, for the first vertex of light subpaths
+}
+
+impl BidirectionalPathTracer {
+    pub fn new(max_depth: usize, heuristic: MisHeuristic) -> Self {
+        Self { max_depth, heuristic }
+    }
+
+    // Extend a subpath from a ray chosen with solid angle density pdf_dir by its last vertex.
+    // Camera subpaths get one vertex more than light subpaths, which already hold the light
+    // point. If the subpath leaves the world, the escaping ray is returned with its throughput and pdf.
+    fn random_walk(&self, scene: &Scene, mut ray: Ray, mut beta: Color, mut pdf_dir: f64, transport: Transport, path: &mut Vec<Vertex>) -> Option<(Ray, Color, f64)> {
+        let first = path.len();
+        let max_vertices = match transport {
+            Transport::Radiance => self.max_depth + 1,
+            Transport::Importance => self.max_depth,
+        };
+
+        while path.len() - first < max_vertices {
+            let rec = match scene.world.hit(&ray, &mut Interval::new(0.001, INFINITY)) {
+                Some(rec) => rec,
+                None => return Some((ray, beta, pdf_dir)),
+            };
+
+            let bsdf = rec.mat.bsdf.clone();
+            let pdf_fwd = to_area(pdf_dir, &ray.orig, &rec);
+            path.push(Vertex { rec, r_in: ray, bsdf: Some(bsdf.clone()), beta, pdf_fwd, pdf_rev: 0.0, delta: false, delta_rev: false, light: None });
+
+            let p = path.last().unwrap().rec.p;
+            if path.len() - first >= max_vertices {
+                update_pdf_rev(path, &p);
+                break;
+            }
+
+            let vertex = path.last().unwrap();
+            let sample = match bsdf.sample(&vertex.r_in, &vertex.rec) {
+                Some(sample) => sample,
+                None => {
+                    update_pdf_rev(path, &p);
+                    break;
+                }
+            };
+
+            let mut scattered = sample.scattered;
+            let mut attenuation = sample.attenuation;
+            if transport == Transport::Importance {
+                attenuation = attenuation * sample.lobe.adjoint_scale(&vertex.r_in, &vertex.rec, &scattered);
+            }
+
+            // Rays after a dispersive lobe only carry their hero wavelength
+            let mut wavelengths = vertex.r_in.wavelengths;
+            if sample.lobe.is_dispersive() {
+                wavelengths.terminate_secondary();
+            }
+            scattered.wavelengths = wavelengths;
+
+            // Whether light coming back along the scattered ray could only reach the previous vertex
+            // through a specular event of the lobe that scattered
+            let reversed = Ray::new(scattered.at(1.0), -scattered.dir);
+            let previous = Ray::new(vertex.rec.p, vertex.r_in.orig - vertex.rec.p);
+            let delta_rev = sample.lobe.is_delta(&reversed, &vertex.rec.reached_by(&reversed), &previous);
+            pdf_dir = if sample.delta { 0.0 } else { sample.pdf };
+
+            let last = path.last_mut().unwrap();
+            last.delta = sample.delta;
+            last.delta_rev = delta_rev;
+
+            // Light comes back to the previous vertex from where the ray left, which is not the
+            // vertex for lobes scattering inside the object
+            update_pdf_rev(path, &scattered.orig);
+
+            beta = beta * wavelengths.from_rgb(&attenuation);
+            if luminance(&beta) <= 0.0 {
+                break;
+            }
+            ray = scattered;
+        }
+
+        None
+    }
+
+    // Light subpath, starting on a point of one of the lights
+    fn light_subpath(&self, scene: &Scene, wavelengths: Wavelengths, path: &mut Vec<Vertex>) {
+        let (light, probability) = match scene.sample_emitter() {
+            Some(choice) => choice,
+            None => return,
+        };
+        let emission = match light.sample_le() {
+            Some(emission) => emission,
+            None => return,
+        };
+        let pdf_pos = probability * emission.pdf_pos;
+        let cos_theta = abs_cos(&emission.rec, &emission.direction);
+
+        let origin = emission.rec.p;
+        path.push(Vertex {
+            rec: emission.rec,
+            r_in: Ray::default(),
+            bsdf: None,
+            beta: Color::new(1.0, 1.0, 1.0) / pdf_pos,
+            pdf_fwd: pdf_pos,
+            pdf_rev: 0.0,
+            delta: false,
+            delta_rev: false,
+            light: Some(light.clone()),
+        });
+
+        if emission.pdf_dir <= 0.0 || luminance(&emission.radiance) <= 0.0 {
+            return;
+        }
+
+        let beta = wavelengths.from_rgb(&emission.radiance) * cos_theta / (pdf_pos * emission.pdf_dir);
+        let mut ray = Ray::new(origin, emission.direction);
+        ray.wavelengths = wavelengths;
+        self.random_walk(scene, ray, beta, emission.pdf_dir, Transport::Importance, path);
+    }
+
+    // Contribution of the strategy using s light vertices and t camera vertices (counting the
+    // camera itself, which is not stored), before MIS weighting
+    fn connect(&self, scene: &Scene, light_path: &[Vertex], camera_path: &[Vertex], s: usize, t: usize) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let pt = &camera_path[t - 2];
+        let wavelengths = &pt.r_in.wavelengths;
+
+        // The camera subpath found a light by itself
+        if s == 0 {
+            return pt.beta * wavelengths.from_rgb(&emission(&pt.rec));
+        }
+
+        let qs = &light_path[s - 1];
+        let pt_bsdf = match &pt.bsdf {
+            Some(bsdf) if !bsdf.is_specular() => bsdf,
+            _ => return black,
+        };
+
+        let direction = qs.rec.p - pt.rec.p;
+        let distance_squared = direction.length_squared();
+        if distance_squared < 1e-12 {
+            return black;
+        }
+
+        let f_pt = pt_bsdf.eval(&pt.r_in, &pt.rec, &Ray::new(pt.rec.p, direction));
+        let f_qs = if let Some(light) = &qs.light {
+            light.le(&qs.rec, &-direction) * abs_cos(&qs.rec, &direction)
+        } else {
+            match &qs.bsdf {
+                Some(bsdf) if !bsdf.is_specular() => bsdf.eval_adjoint(&qs.r_in, &qs.rec, &Ray::new(qs.rec.p, -direction)),
+                _ => return black,
+            }
+        };
+
+        let contribution = qs.beta * wavelengths.from_rgb(&f_qs) * wavelengths.from_rgb(&f_pt) * pt.beta / distance_squared;
+        if luminance(&contribution) <= 0.0 {
+            return black;
+        }
+
+        // Transmittance between the two vertices
+        let distance = distance_squared.sqrt();
+        let shadow_ray = Ray::new(pt.rec.p, direction / distance);
+        let transmittance = scene.world.transmittance(&shadow_ray, &Interval::new(0.001, distance - 0.001));
+        contribution * wavelengths.from_rgb(&transmittance)
+    }
+
+    // Weight of the strategy (s, t) among all the others able to build the same path. Each
+    // alternative differs in where the path is split, so the ratio of their pdfs is obtained
+    // walking away from the connection and swapping forward pdfs for reverse ones.
+    fn mis_weight(&self, scene: &Scene, light_path: &[Vertex], camera_path: &[Vertex], s: usize, t: usize) -> f64 {
+        if s + t == 2 {
+            return 1.0;
+        }
+
+        // Emissive media are not sampled as lights, only the camera subpath can find them
+        let pt = &camera_path[t - 2];
+        if s == 0 && pt.rec.in_medium() {
+            return 1.0;
+        }
+
+        // (pdf_fwd, pdf_rev, delta, delta_rev) of the vertices in use, updated for this connection
+        let mut camera: Vec<(f64, f64, bool, bool)> = camera_path[..t - 1].iter().map(|v| (v.pdf_fwd, v.pdf_rev, v.delta, v.delta_rev)).collect();
+        let mut light: Vec<(f64, f64, bool, bool)> = light_path[..s].iter().map(|v| (v.pdf_fwd, v.pdf_rev, v.delta, v.delta_rev)).collect();
+
+        if s == 0 {
+            // pt acts as the origin of a light subpath, only area lights can be found this way
+            camera[t - 2].1 = scene.light_point_pdf();
+            camera[t - 2].2 = false;
+            camera[t - 2].3 = false;
+            if t >= 3 {
+                if let Some(emission) = &pt.rec.mat.emit {
+                    let previous = &camera_path[t - 3].rec;
+                    let pdf_dir = emission.pdf_direction(&pt.rec.normal, &(previous.p - pt.rec.p));
+                    camera[t - 3].1 = to_area(pdf_dir, &pt.rec.p, previous);
+                }
+            }
+        } else {
+            // The connected vertices scatter through their non specular lobes whatever their subpaths did
+            let qs = &light_path[s - 1];
+            camera[t - 2].2 = false;
+            light[s - 1].2 = false;
+
+            // Light flowing the other way through them may only go on specularly, as it does
+            // when leaving a subsurface object backwards
+            camera[t - 2].3 = t >= 3 && lobe_delta(pt, &qs.rec.p, &camera_path[t - 3].rec.p);
+            light[s - 1].3 = s >= 2 && lobe_delta(qs, &pt.rec.p, &light_path[s - 2].rec.p);
+
+            camera[t - 2].1 = if let Some(light) = &qs.light {
+                to_area(light.pdf_le(&qs.rec, &(pt.rec.p - qs.rec.p)), &qs.rec.p, &pt.rec)
+            } else {
+                lobe_pdf(qs, &qs.r_in.orig, &pt.rec)
+            };
+            if t >= 3 {
+                camera[t - 3].1 = lobe_pdf(pt, &qs.rec.p, &camera_path[t - 3].rec);
+            }
+            light[s - 1].1 = lobe_pdf(pt, &pt.r_in.orig, &qs.rec);
+            if s >= 2 {
+                light[s - 2].1 = lobe_pdf(qs, &pt.rec.p, &light_path[s - 2].rec);
+            }
+        }
+
+        // Light reaches the vertices before them from the connected points, not from where their
+        // subpaths went on
+        if t >= 4 && !camera_path[t - 3].delta_rev {
+            camera[t - 4].1 = lobe_pdf(&camera_path[t - 3], &pt.rec.p, &camera_path[t - 4].rec);
+        }
+        if s >= 3 && !light_path[s - 2].delta_rev {
+            light[s - 3].1 = lobe_pdf(&light_path[s - 2], &light_path[s - 1].rec.p, &light_path[s - 3].rec);
+        }
+
+        // Delta vertices have no density, they are left out of the ratios
+        let remap = |pdf: f64| if pdf != 0.0 { pdf } else { 1.0 };
+        let mut sum = 0.0;
+
+        // Shorter camera subpaths, keeping at least one vertex after the camera
+        let mut ratio = 1.0;
+        for i in (1..t - 1).rev() {
+            ratio *= remap(camera[i].1) / remap(camera[i].0);
+            if !camera[i].3 && !camera[i - 1].2 {
+                sum += self.heuristic.relative(ratio);
+            }
+        }
+
+        // Shorter light subpaths. Point and spot lights cannot be hit, the camera subpath never
+        // reaches them by itself.
+        let delta_light = light_path.first().and_then(|vertex| vertex.light.as_ref()).is_some_and(|light| !light.is_area());
+        ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap(light[i].1) / remap(light[i].0);
+            let previous_delta = if i > 0 { light[i - 1].2 } else { delta_light };
+            if !light[i].3 && !previous_delta {
+                sum += self.heuristic.relative(ratio);
+            }
+        }
+
+        1.0 / (1.0 + sum)
+    }
+}
+
+impl Integrator for BidirectionalPathTracer {
+    fn ray_color(&self, r: &Ray, scene: &Scene) -> Color {
+        let background = scene.background.as_ref();
+        let mut wavelengths = r.wavelengths;
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+
+        let mut camera_path: Vec<Vertex> = Vec::with_capacity(self.max_depth + 1);
+        let escaped = self.random_walk(scene, *r, Color::new(1.0, 1.0, 1.0), 0.0, Transport::Radiance, &mut camera_path);
+
+        // Background, combining the rays escaping the world with explicit samples from the camera vertices
+        if let Some((ray, beta, pdf)) = escaped {
+            let weight = if pdf > 0.0 {
+                self.heuristic.weight(pdf, background.pdf(&ray.dir))
+            } else {
+                1.0
+            };
+            radiance = radiance + beta * weight * wavelengths.from_rgb(&background.value(&ray));
+        }
+
+        let infinite_lights: Vec<&Arc<dyn Light>> = scene.lights.iter().filter(|light| light.is_infinite()).collect();
+        for vertex in camera_path.iter().take(self.max_depth) {
+            if let Some(bsdf) = &vertex.bsdf {
+                if !bsdf.is_specular() {
+                    let mut direct = sample_background(&vertex.r_in, &vertex.rec, bsdf.as_ref(), scene.world, background, self.heuristic);
+                    for light in &infinite_lights {
+                        direct = direct + sample_light(&vertex.r_in, &vertex.rec, bsdf.as_ref(), scene.world, light.as_ref());
+                    }
+                    radiance = radiance + vertex.beta * direct;
+                }
+            }
+        }
+
+        let mut light_path: Vec<Vertex> = Vec::with_capacity(self.max_depth + 1);
+        self.light_subpath(scene, wavelengths, &mut light_path);
+
+        for t in 2..=camera_path.len() + 1 {
+            for s in 0..=light_path.len() {
+                if s + t - 2 > self.max_depth {
+                    break;
+                }
+
+                let contribution = self.connect(scene, &light_path, &camera_path, s, t);
+                if luminance(&contribution) <= 0.0 {
+                    continue;
+                }
+
+                radiance = radiance + self.mis_weight(scene, &light_path, &camera_path, s, t) * contribution;
+            }
+        }
+
+        // The whole sample keeps only its hero wavelength if either subpath went through a dispersive lobe
+        let dispersed = escaped.is_some_and(|(ray, _, _)| ray.wavelengths.is_terminated())
+            || camera_path.iter().chain(light_path.iter()).any(|vertex| vertex.r_in.wavelengths.is_terminated());
+        if dispersed {
+            wavelengths.terminate_secondary();
+        }
+        wavelengths.to_rgb(&radiance)
+    }
+}
+
+impl Default for BidirectionalPathTracer {
+    fn default() -> Self {
+        Self {
+            max_depth: 10,
+            heuristic: MisHeuristic::Power,
+        }
+    }
+}
+
+// Emitted radiance of a point hit, towards the origin of the ray
+fn emission(rec: &HitRecord) -> Color {
+    rec.mat.emitted(rec)
+}
+
+// Convert a solid angle pdf of leaving `from` into an area pdf at the point hit
+fn to_area(pdf_dir: f64, from: &Point3, to: &HitRecord) -> f64 {
+    let w = to.p - *from;
+    let distance_squared = w.length_squared();
+    if distance_squared <= 0.0 {
+        return 0.0;
+    }
+
+    // Points inside media have no surface to project the solid angle onto
+    if to.in_medium() {
+        return pdf_dir / distance_squared;
+    }
+
+    pdf_dir * f64::abs(dot(&to.normal, &w)) / (distance_squared * distance_squared.sqrt())
+}
+
+// Cosine between the normal of a vertex and a direction, one for the points without surface
+fn abs_cos(rec: &HitRecord, direction: &Vec3) -> f64 {
+    if rec.in_medium() {
+        1.0
+    } else {
+        f64::abs(dot(&rec.normal, &direction.unit_vector()))
+    }
+}
+
+// Area pdf at `to` of the vertex non specular lobes scattering towards it, with light arriving from `from`
+fn lobe_pdf(vertex: &Vertex, from: &Point3, to: &HitRecord) -> f64 {
+    let bsdf = match &vertex.bsdf {
+        Some(bsdf) => bsdf,
+        None => return 0.0,
+    };
+
+    let r_in = Ray::new(*from, vertex.rec.p - *from);
+    let rec = vertex.rec.reached_by(&r_in);
+    let scattered = Ray::new(vertex.rec.p, to.p - vertex.rec.p);
+
+    to_area(bsdf.pdf(&r_in, &rec, &scattered), &vertex.rec.p, to)
+}
+
+// Whether the vertex lobes can only scatter light arriving from `from` towards `to` through specular events
+fn lobe_delta(vertex: &Vertex, from: &Point3, to: &Point3) -> bool {
+    let bsdf = match &vertex.bsdf {
+        Some(bsdf) => bsdf,
+        None => return false,
+    };
+
+    let r_in = Ray::new(*from, vertex.rec.p - *from);
+    let rec = vertex.rec.reached_by(&r_in);
+    bsdf.is_delta(&r_in, &rec, &Ray::new(vertex.rec.p, *to - vertex.rec.p))
+}
+
+// Set the reverse pdf of the vertex two before the last one, sampled from the one after it with
+// light arriving there from `from`
+fn update_pdf_rev(path: &mut [Vertex], from: &Point3) {
+    let n = path.len();
+    if n < 3 {
+        return;
+    }
+    let next = &path[n - 2];
+    let pdf_rev = if next.delta_rev { 0.0 } else { lobe_pdf(next, from, &path[n - 3].rec) };
+    path[n - 3].pdf_rev = pdf_rev;
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::integrators::Integrator;
+use crate::scene::Scene;
+use crate::utils::INFINITY;
+
+// Property of the first hit shown by the debug integrator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugView {
+    ShadingNormal,      // Normal used for shading, facing the incoming ray
+    GeometricNormal,    // Outward normal of the surface
+    Uv,                 // u in red, v in green
+    Depth(f64),         // Distance to the hit, white at the given distance and beyond
+    FrontFace,          // Green for front faces, red for back faces
+    MaterialId,         // A different color for each material
+    BvhHeatmap(usize),  // Nodes of the BVH visited, from blue to red at the given count
+}
+
+// Integrator showing what the camera rays hit instead of the light arriving along them. Rays
+// escaping the world are black. Values are squared, so they come out of the gamma encoding of
+// the output unchanged.
+pub struct DebugIntegrator {
+    view: DebugView,
+}
+
+impl DebugIntegrator {
+    pub fn new(view: DebugView) -> Self {
+        Self { view }
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn ray_color(&self, r: &Ray, scene: &Scene) -> Color {
+        let ray_t = Interval::new(0.001, INFINITY);
+        if let DebugView::BvhHeatmap(max_visits) = self.view {
+            let visits = scene.world.bvh_visits(r, &ray_t);
+            return encode(&heatmap(visits as f64 / f64::max(max_visits as f64, 1.0)));
+        }
+
+        let rec = match scene.world.hit(r, &mut Interval::new(ray_t.min, ray_t.max)) {
+            Some(rec) => rec,
+            None => return Color::new(0.0, 0.0, 0.0),
+        };
+
+        let color = match self.view {
+            DebugView::ShadingNormal => 0.5 * (rec.normal + Color::new(1.0, 1.0, 1.0)),
+            DebugView::GeometricNormal => {
+                let outward_normal = if rec.front_face { rec.normal } else { -rec.normal };
+                0.5 * (outward_normal + Color::new(1.0, 1.0, 1.0))
+            }
+            DebugView::Uv => Color::new(rec.u, rec.v, 0.0),
+            DebugView::Depth(max_distance) => {
+                let depth = f64::min(rec.t * r.dir.length() / max_distance, 1.0);
+                Color::new(depth, depth, depth)
+            }
+            DebugView::FrontFace => {
+                if rec.front_face {
+                    Color::new(0.0, 1.0, 0.0)
+                } else {
+                    Color::new(1.0, 0.0, 0.0)
+                }
+            }
+            DebugView::MaterialId => id_color(Arc::as_ptr(&rec.mat) as usize as u64),
+            DebugView::BvhHeatmap(_) => unreachable!(),
+        };
+
+        encode(&color)
+    }
+}
+
+// Undo the gamma 2 applied when writing the image
+fn encode(color: &Color) -> Color {
+    *color * *color
+}
+
+// Blue, cyan, green, yellow and red as t goes from zero to one
+fn heatmap(t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0) * 4.0;
+    let stops = [
+        Color::new(0.0, 0.0, 1.0),
+        Color::new(0.0, 1.0, 1.0),
+        Color::new(0.0, 1.0, 0.0),
+        Color::new(1.0, 1.0, 0.0),
+        Color::new(1.0, 0.0, 0.0),
+    ];
+    let i = usize::min(t as usize, 3);
+    let f = t - i as f64;
+    (1.0 - f) * stops[i] + f * stops[i + 1]
+}
+
+// Bright color scrambled from an identifier
+fn id_color(id: u64) -> Color {
+    // SplitMix64 finalizer
+    let mut x = id.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^= x >> 31;
+
+    let channel = |shift: u32| 0.2 + 0.8 * ((x >> shift) & 0xff) as f64 / 255.0;
+    Color::new(channel(0), channel(8), channel(16))
+}
@@ -0,0 +1,97 @@
+use crate::primitives::*;
+use crate::hittable::{HitRecord, Hittable};
+use crate::backgrounds::Background;
+use crate::lights::Light;
+use crate::materials::ScatteringFunction;
+use crate::integrators::MisHeuristic;
+use crate::scene::Scene;
+use crate::utils::INFINITY;
+
+// Direct lighting from one of the lights, chosen uniformly. Area lights are sampled as a whole:
+// the direction could come from any of them, so whatever emitter the ray reaches first is the one
+// contributing, dimmed by the transmittance of everything in between. The result holds values at
+// the wavelengths of r.
+pub fn sample_lights(r: &Ray, rec: &HitRecord, lobe: &dyn ScatteringFunction, scene: &Scene, heuristic: MisHeuristic) -> Color {
+    let (light, probability) = match scene.sample_light() {
+        Some(choice) => choice,
+        None => return Color::new(0.0, 0.0, 0.0),
+    };
+    if !light.is_area() {
+        return sample_light(r, rec, lobe, scene.world, light.as_ref()) / probability;
+    }
+
+    let shadow_ray = match light.sample_li(&rec.p) {
+        Some(sample) => Ray::new(rec.p, sample.direction),
+        None => return Color::new(0.0, 0.0, 0.0),
+    };
+    let pdf = scene.light_pdf(&rec.p, &shadow_ray.dir);
+    let f = lobe.eval(r, rec, &shadow_ray);
+    if pdf <= 0.0 || luminance(&f) <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let light_rec = match scene.hit_light(&shadow_ray, &Interval::new(0.001, INFINITY)) {
+        Some(light_rec) => light_rec,
+        None => return Color::new(0.0, 0.0, 0.0),
+    };
+    let emit_color = match &light_rec.mat.emit {
+        Some(emission) => emission.radiance(&light_rec),
+        None => return Color::new(0.0, 0.0, 0.0),
+    };
+
+    let transmittance = scene.world.transmittance(&shadow_ray, &Interval::new(0.001, light_rec.t - 0.001));
+    if luminance(&transmittance) <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let wavelengths = &r.wavelengths;
+    let weight = heuristic.weight(pdf, lobe.pdf(r, rec, &shadow_ray));
+    weight * wavelengths.from_rgb(&f) * wavelengths.from_rgb(&transmittance) * wavelengths.from_rgb(&emit_color) / pdf
+}
+
+// Direct lighting from a light rays cannot hit (point, spot or directional), which has no other
+// strategy to be weighted against. The result holds values at the wavelengths of r.
+pub fn sample_light(r: &Ray, rec: &HitRecord, lobe: &dyn ScatteringFunction, world: &dyn Hittable, light: &dyn Light) -> Color {
+    let sample = match light.sample_li(&rec.p) {
+        Some(sample) => sample,
+        None => return Color::new(0.0, 0.0, 0.0),
+    };
+
+    let shadow_ray = Ray::new(rec.p, sample.direction);
+    let f = lobe.eval(r, rec, &shadow_ray);
+    if sample.pdf <= 0.0 || luminance(&f) <= 0.0 || luminance(&sample.radiance) <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let transmittance = world.transmittance(&shadow_ray, &Interval::new(0.001, sample.distance - 0.001));
+    if luminance(&transmittance) <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let wavelengths = &r.wavelengths;
+    wavelengths.from_rgb(&f) * wavelengths.from_rgb(&transmittance) * wavelengths.from_rgb(&sample.radiance) / sample.pdf
+}
+
+// Direct lighting from the background, sampling a direction from it and casting a shadow ray.
+// The result holds values at the wavelengths of r.
+pub fn sample_background(r: &Ray, rec: &HitRecord, lobe: &dyn ScatteringFunction, world: &dyn Hittable, background: &dyn Background, heuristic: MisHeuristic) -> Color {
+    let (direction, pdf) = match background.sample() {
+        Some(sample) => sample,
+        None => return Color::new(0.0, 0.0, 0.0),
+    };
+
+    let shadow_ray = Ray::new(rec.p, direction);
+    let f = lobe.eval(r, rec, &shadow_ray);
+    if pdf <= 0.0 || luminance(&f) <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let transmittance = world.transmittance(&shadow_ray, &Interval::new(0.001, INFINITY));
+    if luminance(&transmittance) <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let wavelengths = &r.wavelengths;
+    let weight = heuristic.weight(pdf, lobe.pdf(r, rec, &shadow_ray));
+    weight * wavelengths.from_rgb(&f) * wavelengths.from_rgb(&transmittance) * wavelengths.from_rgb(&background.value(&shadow_ray)) / pdf
+}
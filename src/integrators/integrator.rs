@@ -0,0 +1,11 @@
+use crate::primitives::{Ray, Color};
+use crate::scene::Scene;
+
+// Light transport algorithm estimating the radiance arriving at the camera along a ray. The
+// result is always RGB, spectral rays are converted back from the wavelengths they carry.
+pub trait Integrator: Send + Sync {
+    fn ray_color(&self, r: &Ray, scene: &Scene) -> Color;
+
+    // Work done once per render before any ray is traced, such as shooting photons
+    fn preprocess(&self, _scene: &Scene) {}
+}
@@ -0,0 +1,31 @@
+// How estimates of the same light coming from different sampling strategies are weighted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MisHeuristic {
+    Balance,
+    Power,
+}
+
+impl MisHeuristic {
+    // Weight of a sample taken with pdf_f when the same path could also come from pdf_g
+    pub fn weight(&self, pdf_f: f64, pdf_g: f64) -> f64 {
+        let (f, g) = match self {
+            MisHeuristic::Balance => (pdf_f, pdf_g),
+            MisHeuristic::Power => (pdf_f * pdf_f, pdf_g * pdf_g),
+        };
+
+        if f + g <= 0.0 {
+            0.0
+        } else {
+            f / (f + g)
+        }
+    }
+
+    // Contribution to the denominator of a weight of a strategy whose pdf is `ratio` times
+    // the one of the strategy being weighted, so weight(f, g) = 1 / (1 + relative(g / f))
+    pub fn relative(&self, ratio: f64) -> f64 {
+        match self {
+            MisHeuristic::Balance => ratio,
+            MisHeuristic::Power => ratio * ratio,
+        }
+    }
+}
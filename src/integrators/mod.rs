@@ -0,0 +1,29 @@
+pub mod integrator;
+pub use self::integrator::Integrator;
+
+pub mod mis;
+pub use self::mis::MisHeuristic;
+
+pub mod direct_lighting;
+pub use self::direct_lighting::{sample_lights, sample_light, sample_background};
+
+pub mod path_tracer;
+pub use self::path_tracer::PathTracer;
+
+pub mod bidirectional;
+pub use self::bidirectional::BidirectionalPathTracer;
+
+pub mod photon_map;
+pub use self::photon_map::{Photon, PhotonMap};
+
+pub mod photon_mapper;
+pub use self::photon_mapper::PhotonMapper;
+
+pub mod whitted;
+pub use self::whitted::WhittedRayTracer;
+
+pub mod ambient_occlusion;
+pub use self::ambient_occlusion::AmbientOcclusion;
+
+pub mod debug;
+pub use self::debug::{DebugIntegrator, DebugView};
\ No newline at end of file
@@ -0,0 +1,117 @@
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::ScatteringFunction;
+use crate::integrators::{Integrator, MisHeuristic, sample_lights, sample_background};
+use crate::scene::Scene;
+use crate::utils::{random_double, INFINITY};
+
+// Unidirectional path tracer. Direct lighting is estimated at every non specular vertex both by
+// sampling the lights and by following the BSDF sample, and the two estimates are combined with
+// multiple importance sampling. Paths are ended by Russian roulette once they have bounced
+// rr_min_depth times, max_depth is only a safety net against paths that never lose energy.
+pub struct PathTracer {
+    max_depth: i32,
+    rr_min_depth: i32,
+    heuristic: MisHeuristic,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: i32, rr_min_depth: i32, heuristic: MisHeuristic) -> Self {
+        Self { max_depth, rr_min_depth, heuristic }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn ray_color(&self, r: &Ray, scene: &Scene) -> Color {
+        let world = scene.world;
+        let background = scene.background.as_ref();
+        let heuristic = self.heuristic;
+
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray: Ray = *r;
+        let mut wavelengths = r.wavelengths;
+
+        // Solid angle pdf with which the previous vertex chose the current ray, or zero when
+        // that vertex was specular (or the camera) and no light was sampled from it
+        let mut bsdf_pdf: f64 = 0.0;
+
+        for bounce in 0..self.max_depth {
+            let hit_record: HitRecord = match world.hit(&ray, &mut Interval::new(0.001, INFINITY)) {
+                Some(rec) => rec,
+                None => {
+                    let weight = if bsdf_pdf > 0.0 {
+                        heuristic.weight(bsdf_pdf, background.pdf(&ray.dir))
+                    } else {
+                        1.0
+                    };
+                    radiance = radiance + throughput * weight * wavelengths.from_rgb(&background.value(&ray));
+                    break;
+                }
+            };
+
+            let material = &hit_record.mat;
+
+            // Add emission if any
+            if let Some(emission) = &material.emit {
+                let emit_color = emission.radiance(&hit_record);
+                // Emissive media are not sampled as lights, nothing to weight against
+                let weight = if bsdf_pdf > 0.0 && !hit_record.in_medium() {
+                    heuristic.weight(bsdf_pdf, scene.light_pdf(&ray.orig, &ray.dir))
+                } else {
+                    1.0
+                };
+                radiance = radiance + throughput * weight * wavelengths.from_rgb(&emit_color);
+            }
+
+            let bsdf = material.bsdf.as_ref();
+            let sample = match bsdf.sample(&ray, &hit_record) {
+                Some(sample) => sample,
+                // Absorption or no scattering
+                None => break,
+            };
+
+            if !bsdf.is_specular() {
+                let direct = sample_lights(&ray, &hit_record, bsdf, scene, heuristic)
+                    + sample_background(&ray, &hit_record, bsdf, world, background, heuristic);
+                radiance = radiance + throughput * direct;
+            }
+            bsdf_pdf = sample.pdf;
+
+            if sample.lobe.is_dispersive() {
+                wavelengths.terminate_secondary();
+            }
+            let mut scattered_ray = sample.scattered;
+            scattered_ray.wavelengths = wavelengths;
+            throughput = throughput * wavelengths.from_rgb(&sample.attenuation);
+
+            // Russian roulette: dim paths are ended randomly and the survivors are boosted
+            // by the inverse of the survival probability, which keeps the estimate unbiased
+            if bounce + 1 >= self.rr_min_depth {
+                let survival = f64::min(max_component(&throughput), 1.0);
+                if random_double() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+
+            ray = scattered_ray;
+        }
+
+        wavelengths.to_rgb(&radiance)
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self {
+            max_depth: 64,
+            rr_min_depth: 3,
+            heuristic: MisHeuristic::Power,
+        }
+    }
+}
+
+pub(crate) fn max_component(c: &Color) -> f64 {
+    f64::max(c.x, f64::max(c.y, c.z))
+}
@@ -0,0 +1,193 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::primitives::*;
+
+// Light flux deposited on a surface by a photon
+#[derive(Clone, Copy)]
+pub struct Photon {
+    pub position: Point3,
+    pub direction: Vec3,    // Direction the photon was travelling in
+    pub power: Color,
+    axis: usize,            // Splitting axis of the kd-tree node holding the photon
+}
+
+impl Photon {
+    pub fn new(position: Point3, direction: Vec3, power: Color) -> Self {
+        Self { position, direction, power, axis: 0 }
+    }
+}
+
+// Photons stored in a balanced kd-tree. The tree is implicit: every subslice has its node in the
+// middle, with the photons below the splitting plane on its left and the ones above on its right.
+#[derive(Default)]
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+}
+
+// Photon found by a nearest neighbours search, ordered by distance
+struct Neighbor {
+    distance_squared: f64,
+    index: usize,
+}
+
+impl PartialEq for Neighbor {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance_squared == other.distance_squared
+    }
+}
+
+impl Eq for Neighbor {}
+
+impl PartialOrd for Neighbor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Neighbor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared.total_cmp(&other.distance_squared)
+    }
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>) -> Self {
+        build(&mut photons);
+        Self { photons }
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    // The k photons closest to a point, not further than max_distance. Returns them with the
+    // squared radius of the sphere containing them, which is max_distance squared when less
+    // than k photons were found.
+    pub fn nearest(&self, point: &Point3, k: usize, max_distance: f64) -> (Vec<&Photon>, f64) {
+        let mut heap: BinaryHeap<Neighbor> = BinaryHeap::with_capacity(k + 1);
+        let mut radius_squared = max_distance * max_distance;
+        self.search(0, self.photons.len(), point, k, &mut radius_squared, &mut heap);
+
+        let found = heap.iter().map(|neighbor| &self.photons[neighbor.index]).collect();
+        (found, radius_squared)
+    }
+
+    fn search(&self, start: usize, end: usize, point: &Point3, k: usize, radius_squared: &mut f64, heap: &mut BinaryHeap<Neighbor>) {
+        if start >= end || k == 0 {
+            return;
+        }
+
+        let mid = start + (end - start) / 2;
+        let photon = &self.photons[mid];
+        let offset = point[photon.axis] - photon.position[photon.axis];
+
+        // Visit first the side of the plane containing the point, the other one only if the
+        // search sphere crosses the plane
+        let (near, far) = if offset < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+
+        self.search(near.0, near.1, point, k, radius_squared, heap);
+
+        let distance_squared = (photon.position - *point).length_squared();
+        if distance_squared < *radius_squared {
+            heap.push(Neighbor { distance_squared, index: mid });
+            if heap.len() > k {
+                heap.pop();
+            }
+            if heap.len() == k {
+                *radius_squared = heap.peek().unwrap().distance_squared;
+            }
+        }
+
+        if offset * offset < *radius_squared {
+            self.search(far.0, far.1, point, k, radius_squared, heap);
+        }
+    }
+}
+
+// Arrange the photons of a slice as a kd-tree, splitting at the median of the longest axis
+fn build(photons: &mut [Photon]) {
+    if photons.len() <= 1 {
+        return;
+    }
+
+    let mut min = photons[0].position;
+    let mut max = photons[0].position;
+    for photon in photons.iter() {
+        for axis in 0..3 {
+            min[axis] = f64::min(min[axis], photon.position[axis]);
+            max[axis] = f64::max(max[axis], photon.position[axis]);
+        }
+    }
+    let extent = max - min;
+    let axis = if extent.x > extent.y && extent.x > extent.z {
+        0
+    } else if extent.y > extent.z {
+        1
+    } else {
+        2
+    };
+
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| a.position[axis].total_cmp(&b.position[axis]));
+    photons[mid].axis = axis;
+
+    let (left, right) = photons.split_at_mut(mid);
+    build(left);
+    build(&mut right[1..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random_double;
+
+    fn random_point() -> Point3 {
+        Point3::new(random_double() * 10.0, random_double() * 2.0, random_double() * 5.0)
+    }
+
+    // Squared distances to the point of the k closest photons within max_distance, in order
+    fn brute_force(photons: &[Photon], point: &Point3, k: usize, max_distance: f64) -> Vec<f64> {
+        let mut distances: Vec<f64> = photons
+            .iter()
+            .map(|photon| (photon.position - *point).length_squared())
+            .filter(|&distance_squared| distance_squared < max_distance * max_distance)
+            .collect();
+        distances.sort_by(f64::total_cmp);
+        distances.truncate(k);
+        distances
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let color = Color::new(1.0, 1.0, 1.0);
+        let photons: Vec<Photon> = (0..2000).map(|_| Photon::new(random_point(), Vec3::new(0.0, -1.0, 0.0), color)).collect();
+        let map = PhotonMap::new(photons.clone());
+        assert_eq!(map.len(), photons.len());
+
+        for (k, max_distance) in [(1, 100.0), (10, 100.0), (50, 1.0), (200, 0.5), (5000, 100.0)] {
+            for _ in 0..50 {
+                let point = random_point();
+                let (found, radius_squared) = map.nearest(&point, k, max_distance);
+                let mut distances: Vec<f64> = found.iter().map(|photon| (photon.position - point).length_squared()).collect();
+                distances.sort_by(f64::total_cmp);
+
+                let expected = brute_force(&photons, &point, k, max_distance);
+                assert_eq!(distances, expected);
+                if expected.len() == k {
+                    assert_eq!(radius_squared, expected[k - 1]);
+                } else {
+                    assert_eq!(radius_squared, max_distance * max_distance);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::f64::consts::PI;
+use std::sync::RwLock;
+use rayon::prelude::*;
+
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::ScatteringFunction;
+use crate::integrators::{Integrator, MisHeuristic, Photon, PhotonMap, sample_lights, sample_background};
+use crate::integrators::path_tracer::max_component;
+use crate::scene::Scene;
+use crate::spectrum::Wavelengths;
+use crate::utils::{random_double, INFINITY};
+
+// Path tracer with a caustics photon map. Before rendering, photons are shot from the lights
+// (but the directional ones, infinitely far away) and the ones reaching a surface after bouncing
+// on mirrors or glass are stored in a kd-tree. At every non specular vertex of a camera path the
+// caustic radiance is estimated from the photons closest to it, and the paths that would find
+// those caustics by themselves (non specular vertex, then only specular bounces until a light)
+// are not counted again.
+pub struct PhotonMapper {
+    photons: usize,         // Photons shot from the lights
+    nearest: usize,         // Photons used by each radiance estimate
+    max_radius: f64,        // Largest distance at which a photon is gathered
+    max_depth: i32,
+    rr_min_depth: i32,
+    heuristic: MisHeuristic,
+    caustics: RwLock<PhotonMap>,
+}
+
+impl PhotonMapper {
+    pub fn new(photons: usize, nearest: usize, max_radius: f64, max_depth: i32, rr_min_depth: i32, heuristic: MisHeuristic) -> Self {
+        Self {
+            photons,
+            nearest,
+            max_radius,
+            max_depth,
+            rr_min_depth,
+            heuristic,
+            caustics: RwLock::new(PhotonMap::default()),
+        }
+    }
+
+    // Follow a photon leaving a random light through the specular surfaces it
+    // meets, storing it on every surface reached after at least one specular bounce
+    fn trace_photon(&self, scene: &Scene) -> Vec<Photon> {
+        let mut stored = Vec::new();
+
+        let (light, probability) = match scene.sample_emitter() {
+            Some(choice) => choice,
+            None => return stored,
+        };
+        let emission = match light.sample_le() {
+            Some(emission) if emission.pdf_dir > 0.0 => emission,
+            _ => return stored,
+        };
+
+        // Point and spot lights have no surface to project the emitted light onto
+        let cos_theta = if emission.rec.in_medium() {
+            1.0
+        } else {
+            f64::abs(dot(&emission.rec.normal, &emission.direction))
+        };
+
+        // Spectral photons carry their own wavelengths and are stored back in RGB
+        let mut wavelengths = if scene.spectral { Wavelengths::sample() } else { Wavelengths::Rgb };
+        let mut power = wavelengths.from_rgb(&emission.radiance) * cos_theta
+            / (probability * emission.pdf_pos * emission.pdf_dir * self.photons as f64);
+        let mut ray = Ray::new(emission.rec.p, emission.direction);
+        ray.wavelengths = wavelengths;
+
+        for bounce in 0..self.max_depth {
+            let rec = match scene.world.hit(&ray, &mut Interval::new(0.001, INFINITY)) {
+                Some(rec) => rec,
+                None => break,
+            };
+
+            // Only surfaces gathering photons store them, the ones left on other surfaces would
+            // leak onto their neighbours
+            if bounce > 0 && !rec.in_medium() && gathers(&ray, &rec) {
+                stored.push(Photon::new(rec.p, ray.dir.unit_vector(), wavelengths.to_rgb(&power)));
+            }
+
+            // Photons only stand for the paths camera paths leave out, the ones going back to the
+            // light through specular events alone. Light entering a subsurface object goes on
+            // specularly, but the camera paths leaving it the other way do not.
+            let sample = match rec.mat.bsdf.sample(&ray, &rec) {
+                Some(sample) if sample.delta => sample,
+                _ => break,
+            };
+            let reversed = Ray::new(sample.scattered.at(1.0), -sample.scattered.dir);
+            if !sample.lobe.is_delta(&reversed, &rec.reached_by(&reversed), &Ray::new(rec.p, -ray.dir)) {
+                break;
+            }
+
+            if sample.lobe.is_dispersive() {
+                wavelengths.terminate_secondary();
+            }
+            let mut scattered = sample.scattered;
+            scattered.wavelengths = wavelengths;
+            power = power * wavelengths.from_rgb(&sample.attenuation) * sample.lobe.adjoint_scale(&ray, &rec, &scattered);
+            if luminance(&power) <= 0.0 {
+                break;
+            }
+            ray = scattered;
+        }
+
+        stored
+    }
+
+    // Radiance leaving a non specular vertex towards the camera carried by the nearby caustic photons
+    fn caustic_radiance(&self, caustics: &PhotonMap, r: &Ray, rec: &HitRecord, lobe: &dyn ScatteringFunction) -> Color {
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        if rec.in_medium() {
+            return radiance;
+        }
+
+        let (photons, radius_squared) = caustics.nearest(&rec.p, self.nearest, self.max_radius);
+        if photons.is_empty() || radius_squared <= 0.0 {
+            return radiance;
+        }
+
+        for photon in photons {
+            // eval includes the cosine at the vertex, the flux of the photon already does
+            let towards_light = Ray::new(rec.p, -photon.direction);
+            let cos_theta = f64::abs(dot(&rec.normal, &towards_light.dir));
+            if cos_theta <= 0.0 {
+                continue;
+            }
+            radiance = radiance + lobe.eval(r, rec, &towards_light) * photon.power / cos_theta;
+        }
+
+        radiance / (PI * radius_squared)
+    }
+}
+
+impl Integrator for PhotonMapper {
+    fn preprocess(&self, scene: &Scene) {
+        let photons: Vec<Photon> = (0..self.photons)
+            .into_par_iter()
+            .flat_map_iter(|_| self.trace_photon(scene))
+            .collect();
+
+        *self.caustics.write().unwrap() = PhotonMap::new(photons);
+    }
+
+    fn ray_color(&self, r: &Ray, scene: &Scene) -> Color {
+        let world = scene.world;
+        let background = scene.background.as_ref();
+        let heuristic = self.heuristic;
+        let caustics = self.caustics.read().unwrap();
+
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray: Ray = *r;
+        let mut wavelengths = r.wavelengths;
+
+        // Solid angle pdf with which the previous vertex chose the current ray, or zero when
+        // that vertex was specular (or the camera) and no light was sampled from it
+        let mut bsdf_pdf: f64 = 0.0;
+
+        // Caustics were gathered at the last non specular vertex and only specular ones followed
+        // it, so any light reached now was already accounted by the photon map. Points inside
+        // media do not gather caustics.
+        let mut caustic_path = false;
+        let mut gathered = false;
+
+        for bounce in 0..self.max_depth {
+            let hit_record: HitRecord = match world.hit(&ray, &mut Interval::new(0.001, INFINITY)) {
+                Some(rec) => rec,
+                None => {
+                    let weight = if bsdf_pdf > 0.0 {
+                        heuristic.weight(bsdf_pdf, background.pdf(&ray.dir))
+                    } else {
+                        1.0
+                    };
+                    radiance = radiance + throughput * weight * wavelengths.from_rgb(&background.value(&ray));
+                    break;
+                }
+            };
+
+            let material = &hit_record.mat;
+
+            // Add emission if any
+            if let Some(emission) = &material.emit {
+                let emit_color = emission.radiance(&hit_record);
+                // Photons are only shot from surfaces, emissive media are always counted here
+                if !caustic_path || hit_record.in_medium() {
+                    let weight = if bsdf_pdf > 0.0 && !hit_record.in_medium() {
+                        heuristic.weight(bsdf_pdf, scene.light_pdf(&ray.orig, &ray.dir))
+                    } else {
+                        1.0
+                    };
+                    radiance = radiance + throughput * weight * wavelengths.from_rgb(&emit_color);
+                }
+            }
+
+            let bsdf = material.bsdf.as_ref();
+            let sample = match bsdf.sample(&ray, &hit_record) {
+                Some(sample) => sample,
+                // Absorption or no scattering
+                None => break,
+            };
+
+            if !bsdf.is_specular() {
+                let caustic = self.caustic_radiance(&caustics, &ray, &hit_record, bsdf);
+                let direct = sample_lights(&ray, &hit_record, bsdf, scene, heuristic)
+                    + sample_background(&ray, &hit_record, bsdf, world, background, heuristic)
+                    + wavelengths.from_rgb(&caustic);
+                radiance = radiance + throughput * direct;
+            }
+
+            // Caustics gathered here only cover light scattered by the non specular lobes, a
+            // specular bounce keeps following the caustic path of the vertex before
+            if sample.delta {
+                bsdf_pdf = 0.0;
+                caustic_path = gathered;
+            } else {
+                bsdf_pdf = sample.pdf;
+                caustic_path = false;
+                gathered = !hit_record.in_medium();
+            }
+
+            if sample.lobe.is_dispersive() {
+                wavelengths.terminate_secondary();
+            }
+            let mut scattered_ray = sample.scattered;
+            scattered_ray.wavelengths = wavelengths;
+            throughput = throughput * wavelengths.from_rgb(&sample.attenuation);
+
+            // Russian roulette, as in the path tracer
+            if bounce + 1 >= self.rr_min_depth {
+                let survival = f64::min(max_component(&throughput), 1.0);
+                if random_double() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+
+            ray = scattered_ray;
+        }
+
+        wavelengths.to_rgb(&radiance)
+    }
+}
+
+impl Default for PhotonMapper {
+    fn default() -> Self {
+        Self::new(1_000_000, 100, 5.0, 64, 3, MisHeuristic::Power)
+    }
+}
+
+// Whether a camera path reaching the point from either side could scatter the photon arriving
+// along r_in through a non specular event and gather it
+fn gathers(r_in: &Ray, rec: &HitRecord) -> bool {
+    let outward_normal = if rec.front_face { rec.normal } else { -rec.normal };
+    let towards_light = Ray::new(rec.p, -r_in.dir);
+
+    [outward_normal, -outward_normal].iter().any(|normal| {
+        let camera = Ray::new(rec.p + *normal, -*normal);
+        !rec.mat.bsdf.is_delta(&camera, &rec.reached_by(&camera), &towards_light)
+    })
+}
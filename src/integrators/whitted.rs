@@ -0,0 +1,87 @@
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::ScatteringFunction;
+use crate::integrators::Integrator;
+use crate::scene::Scene;
+use crate::utils::INFINITY;
+
+// Classic Whitted ray tracer, without any randomness. Non specular lobes are lit directly by
+// every light taken as a single point at its center, with hard shadows from anything in between.
+// Specular lobes are followed into every direction they scatter into (both the reflected and the
+// refracted ray for glass) until max_depth. Indirect light between diffuse surfaces and light from
+// the background on them are missing.
+pub struct WhittedRayTracer {
+    max_depth: i32,
+}
+
+impl WhittedRayTracer {
+    pub fn new(max_depth: i32) -> Self {
+        Self { max_depth }
+    }
+
+    // RGB radiance arriving along r, weighted by throughput (values at the wavelengths of r)
+    fn trace(&self, r: &Ray, throughput: Color, scene: &Scene, depth: i32) -> Color {
+        let wavelengths = r.wavelengths;
+        let rec: HitRecord = match scene.world.hit(r, &mut Interval::new(0.001, INFINITY)) {
+            Some(rec) => rec,
+            None => return wavelengths.to_rgb(&(throughput * wavelengths.from_rgb(&scene.background.value(r)))),
+        };
+
+        let material = &rec.mat;
+        let mut radiance = wavelengths.to_rgb(&(throughput * wavelengths.from_rgb(&material.emitted(&rec))));
+
+        let bsdf = material.bsdf.as_ref();
+        if !bsdf.is_specular() {
+            let direct = self.direct_lighting(r, &rec, bsdf, scene);
+            radiance = radiance + wavelengths.to_rgb(&(throughput * direct));
+        }
+
+        if depth + 1 >= self.max_depth {
+            return radiance;
+        }
+        for (attenuation, scattered) in bsdf.specular_directions(r, &rec) {
+            let weight = throughput * wavelengths.from_rgb(&attenuation);
+            if weight.length_squared() > 0.0 {
+                radiance = radiance + self.trace(&scattered, weight, scene, depth + 1);
+            }
+        }
+
+        radiance
+    }
+
+    // Light reaching the point from the center of every light and scattered by the lobe towards
+    // the origin of r, as values at the wavelengths of r
+    fn direct_lighting(&self, r: &Ray, rec: &HitRecord, lobe: &dyn ScatteringFunction, scene: &Scene) -> Color {
+        let mut direct = Color::new(0.0, 0.0, 0.0);
+        for light in &scene.lights {
+            let sample = match light.sample_li_center(&rec.p) {
+                Some(sample) => sample,
+                None => continue,
+            };
+
+            let shadow_ray = Ray::new(rec.p, sample.direction);
+            let f = lobe.eval(r, rec, &shadow_ray);
+            if f.length_squared() <= 0.0 {
+                continue;
+            }
+            if scene.world.hit(&shadow_ray, &mut Interval::new(0.001, sample.distance - 0.001)).is_some() {
+                continue;
+            }
+
+            direct = direct + r.wavelengths.from_rgb(&f) * r.wavelengths.from_rgb(&sample.radiance) / sample.pdf;
+        }
+        direct
+    }
+}
+
+impl Default for WhittedRayTracer {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl Integrator for WhittedRayTracer {
+    fn ray_color(&self, r: &Ray, scene: &Scene) -> Color {
+        self.trace(r, Color::new(1.0, 1.0, 1.0), scene, 0)
+    }
+}
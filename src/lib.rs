@@ -6,7 +6,13 @@ pub mod primitives;
 pub mod utils;
 pub mod bvh;
 pub mod textures;
+pub mod backgrounds;
+pub mod integrators;
+pub mod scene;
+pub mod media;
 pub mod external;
+pub mod spectrum;
+pub mod lights;
 
 pub use materials::*;
 pub use primitives::*;
@@ -14,4 +20,10 @@ pub use hittable::*;
 pub use camera::*;
 pub use bvh::*;
 pub use textures::*;
-pub use external::*;
\ No newline at end of file
+pub use backgrounds::*;
+pub use integrators::*;
+pub use scene::*;
+pub use media::*;
+pub use external::*;
+pub use spectrum::*;
+pub use lights::*;
\ No newline at end of file
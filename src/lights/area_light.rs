@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::hittable::{Hittable, HitRecord};
+use crate::lights::{Light, LightSample, LightEmission};
+use crate::utils::INFINITY;
+
+// Emissive primitive of the world, emitting as its material says
+pub struct AreaLight {
+    shape: Arc<dyn Hittable + Send + Sync>,
+}
+
+impl AreaLight {
+    pub fn new(shape: Arc<dyn Hittable + Send + Sync>) -> Self {
+        Self { shape }
+    }
+}
+
+impl Light for AreaLight {
+    fn sample_li(&self, p: &Point3) -> Option<LightSample> {
+        let direction = self.shape.random(p).unit_vector();
+        let pdf = self.shape.pdf_value(p, &direction);
+        let rec = self.shape.hit(&Ray::new(*p, direction), &mut Interval::new(0.001, INFINITY))?;
+        let radiance = rec.mat.emit.as_ref()?.radiance(&rec);
+
+        Some(LightSample { direction, distance: rec.t, radiance, pdf })
+    }
+
+    // Towards the center of the bounding box, up to the surface facing p. The radiance found there
+    // is spread over the solid angle the light covers.
+    fn sample_li_center(&self, p: &Point3) -> Option<LightSample> {
+        let bbox = self.shape.bounding_box();
+        let center = Point3::new(
+            (bbox.x.min + bbox.x.max) / 2.0,
+            (bbox.y.min + bbox.y.max) / 2.0,
+            (bbox.z.min + bbox.z.max) / 2.0,
+        );
+        let to_center = center - *p;
+        if to_center.near_zero() {
+            return None;
+        }
+
+        let direction = to_center.unit_vector();
+        let pdf = self.shape.pdf_value(p, &direction);
+        let rec = self.shape.hit(&Ray::new(*p, direction), &mut Interval::new(0.001, INFINITY))?;
+        let radiance = rec.mat.emit.as_ref()?.radiance(&rec);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample { direction, distance: rec.t, radiance: radiance / pdf, pdf: 1.0 })
+    }
+
+    fn pdf_li(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.shape.pdf_value(origin, direction)
+    }
+
+    fn sample_le(&self) -> Option<LightEmission> {
+        let rec = self.shape.random_point()?;
+        let emission = rec.mat.emit.clone()?;
+
+        let direction = emission.sample_direction(&rec.normal);
+        let pdf_dir = emission.pdf_direction(&rec.normal, &direction);
+        let radiance = emission.radiance_towards(&rec, &direction);
+
+        Some(LightEmission { rec, direction, radiance, pdf_pos: 1.0 / self.shape.area(), pdf_dir })
+    }
+
+    fn le(&self, rec: &HitRecord, direction: &Vec3) -> Color {
+        match &rec.mat.emit {
+            Some(emission) => emission.radiance_towards(rec, direction),
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn pdf_le(&self, rec: &HitRecord, direction: &Vec3) -> f64 {
+        match &rec.mat.emit {
+            Some(emission) => emission.pdf_direction(&rec.normal, direction),
+            None => 0.0,
+        }
+    }
+
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        self.shape.hit(r, ray_t)
+    }
+
+    fn is_area(&self) -> bool {
+        true
+    }
+
+    fn area(&self) -> f64 {
+        self.shape.area()
+    }
+}
@@ -0,0 +1,61 @@
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::lights::{Light, LightSample, LightEmission, sample_cone, cone_pdf};
+use crate::utils::{degrees_to_radians, INFINITY};
+
+// Light coming from infinitely far away, like the sun. With an angular radius (in degrees, about
+// 0.27 for the sun) it arrives from a small disk in the sky and casts soft shadows, with none it
+// arrives from a single direction.
+pub struct DirectionalLight {
+    direction: Vec3,        // Direction the light travels along
+    irradiance: Color,      // Light arriving at a surface facing it
+    cos_theta_max: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, irradiance: Color, angular_radius: f64) -> Self {
+        let angular_radius = f64::clamp(angular_radius, 0.0, 90.0);
+        Self {
+            direction: direction.unit_vector(),
+            irradiance,
+            cos_theta_max: f64::cos(degrees_to_radians(angular_radius)),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _p: &Point3) -> Option<LightSample> {
+        if self.cos_theta_max >= 1.0 {
+            return Some(LightSample { direction: -self.direction, distance: INFINITY, radiance: self.irradiance, pdf: 1.0 });
+        }
+
+        // Uniform over the disk, with the radiance giving the irradiance once integrated over it
+        let pdf = cone_pdf(self.cos_theta_max);
+        Some(LightSample {
+            direction: sample_cone(&-self.direction, self.cos_theta_max),
+            distance: INFINITY,
+            radiance: self.irradiance * pdf,
+            pdf,
+        })
+    }
+
+    fn sample_li_center(&self, _p: &Point3) -> Option<LightSample> {
+        Some(LightSample { direction: -self.direction, distance: INFINITY, radiance: self.irradiance, pdf: 1.0 })
+    }
+
+    fn sample_le(&self) -> Option<LightEmission> {
+        None
+    }
+
+    fn le(&self, _rec: &HitRecord, _direction: &Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    fn pdf_le(&self, _rec: &HitRecord, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,95 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::{Material, Lambertian, Specular, Refractive};
+use crate::utils::random_double;
+
+// Light arriving at a point from a light, as returned by Light::sample_li
+pub struct LightSample {
+    pub direction: Vec3,    // Unit direction from the point towards the light
+    pub distance: f64,      // Distance along direction to the light, infinite for directional lights
+    pub radiance: Color,    // Radiance arriving along direction, or irradiance for lights without extent
+    pub pdf: f64,           // Solid angle pdf of direction, one for lights without extent
+}
+
+// Light leaving a light, as returned by Light::sample_le to start light subpaths and photons
+pub struct LightEmission {
+    pub rec: HitRecord,     // Point of the light, with a zero normal when it has no surface
+    pub direction: Vec3,    // Unit direction of the emitted light
+    pub radiance: Color,    // Radiance along direction, or intensity for point and spot lights
+    pub pdf_pos: f64,       // Area pdf of the point, one for point and spot lights
+    pub pdf_dir: f64,       // Solid angle pdf of direction
+}
+
+// Source of light in the scene. Area lights are emissive primitives of the world and rays can hit
+// them, the other lights are only found by sampling them.
+pub trait Light: Send + Sync {
+    // Sample the light arriving at p from the light, not accounting for anything in between
+    fn sample_li(&self, p: &Point3) -> Option<LightSample>;
+
+    // Light arriving at p as if the whole light were at its center, for integrators that do not
+    // sample. The radiance is the irradiance it brings, so the pdf is always one.
+    fn sample_li_center(&self, p: &Point3) -> Option<LightSample> {
+        self.sample_li(p)
+    }
+
+    // Solid angle pdf with which sample_li would choose direction from origin
+    fn pdf_li(&self, _origin: &Point3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    // Sample a point of the light and a direction leaving it. Lights infinitely far away return None.
+    fn sample_le(&self) -> Option<LightEmission>;
+
+    // Radiance (intensity for point and spot lights) leaving a point of the light along direction
+    fn le(&self, rec: &HitRecord, direction: &Vec3) -> Color;
+
+    // Solid angle pdf with which sample_le would emit along direction from a point of the light
+    fn pdf_le(&self, rec: &HitRecord, direction: &Vec3) -> f64;
+
+    // First point of the light hit by the ray within ray_t, only area lights can be hit
+    fn hit(&self, _r: &Ray, _ray_t: &mut Interval) -> Option<HitRecord> {
+        None
+    }
+
+    fn is_area(&self) -> bool {
+        false
+    }
+
+    // Lights infinitely far away, which do not start light subpaths nor shoot photons
+    fn is_infinite(&self) -> bool {
+        false
+    }
+
+    // Surface area of the light, zero for the lights without one
+    fn area(&self) -> f64 {
+        0.0
+    }
+}
+
+// Material of the points of lights without surface, which neither scatter nor emit by themselves
+pub(crate) fn no_surface() -> Arc<Material> {
+    let black = Lambertian::new(Color::new(0.0, 0.0, 0.0));
+    Arc::new(Material::new(black, Specular::new(), Refractive::new(1.0), None, 0.0, 0.0, 0.0, 1.0))
+}
+
+// Direction uniformly distributed inside the cone of half angle acos(cos_theta_max) around axis
+pub(crate) fn sample_cone(axis: &Vec3, cos_theta_max: f64) -> Vec3 {
+    let w = axis.unit_vector();
+    let a = if f64::abs(w.x) > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let v = w.cross(&a).unit_vector();
+    let u = w.cross(&v);
+
+    let z = 1.0 + random_double() * (cos_theta_max - 1.0);
+    let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - z * z));
+    let phi = 2.0 * PI * random_double();
+
+    f64::cos(phi) * sin_theta * u + f64::sin(phi) * sin_theta * v + z * w
+}
+
+// Solid angle pdf of sample_cone
+pub(crate) fn cone_pdf(cos_theta_max: f64) -> f64 {
+    1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+}
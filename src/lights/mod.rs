@@ -0,0 +1,15 @@
+pub mod light;
+pub use self::light::{Light, LightSample, LightEmission};
+pub(crate) use self::light::{no_surface, sample_cone, cone_pdf};
+
+pub mod area_light;
+pub use self::area_light::AreaLight;
+
+pub mod point_light;
+pub use self::point_light::PointLight;
+
+pub mod spot_light;
+pub use self::spot_light::SpotLight;
+
+pub mod directional_light;
+pub use self::directional_light::DirectionalLight;
@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::Material;
+use crate::lights::{Light, LightSample, LightEmission, no_surface};
+
+// Light emitted from a single point with the same intensity in every direction
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,   // Power per unit solid angle
+    mat: Arc<Material>,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self { position, intensity, mat: no_surface() }
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, p: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / distance_squared,
+            pdf: 1.0,
+        })
+    }
+
+    fn sample_le(&self) -> Option<LightEmission> {
+        let rec = HitRecord::new(self.position, Vec3::new(0.0, 0.0, 0.0), self.mat.clone(), 0.0, 0.0, 0.0, true);
+        Some(LightEmission {
+            rec,
+            direction: Vec3::random_unit_vector(),
+            radiance: self.intensity,
+            pdf_pos: 1.0,
+            pdf_dir: 1.0 / (4.0 * PI),
+        })
+    }
+
+    fn le(&self, _rec: &HitRecord, _direction: &Vec3) -> Color {
+        self.intensity
+    }
+
+    fn pdf_le(&self, _rec: &HitRecord, _direction: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::HittableList;
+    use crate::integrators::sample_light;
+    use crate::materials::scattering_function::Lambertian;
+
+    #[test]
+    fn lambertian_surface_gets_intensity_over_distance_squared() {
+        let albedo = 0.6;
+        let material = Arc::new(Material::new_from_lobe(Lambertian::new(Color::new(albedo, albedo, albedo)), None));
+        let rec = HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), material.clone(), 1.0, 0.0, 0.0, true);
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 1.0), Vec3::new(0.0, -1.0, -1.0));
+        let world = HittableList::new();
+
+        let intensity = 50.0;
+        for (position, cos_theta) in [(Point3::new(0.0, 2.0, 0.0), 1.0), (Point3::new(3.0, 4.0, 0.0), 0.8)] {
+            let light = PointLight::new(position, Color::new(intensity, intensity, intensity));
+            let distance_squared = position.length_squared();
+
+            let sample = light.sample_li(&rec.p).unwrap();
+            assert!((sample.radiance.x - intensity / distance_squared).abs() < 1e-12);
+
+            // Radiance reflected by the Lambertian surface, albedo / π times the irradiance
+            let reflected = sample_light(&r_in, &rec, material.bsdf.as_ref(), &world, &light);
+            let expected = albedo / PI * intensity * cos_theta / distance_squared;
+            assert!((reflected.x - expected).abs() < 1e-12, "{} != {}", reflected.x, expected);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::Material;
+use crate::lights::{Light, LightSample, LightEmission, no_surface, sample_cone, cone_pdf};
+use crate::utils::degrees_to_radians;
+
+// Point light emitting inside a cone. The intensity is full up to falloff_start degrees from the
+// axis and fades smoothly to zero at total_width degrees.
+pub struct SpotLight {
+    position: Point3,
+    axis: Vec3,
+    intensity: Color,
+    cos_total_width: f64,
+    cos_falloff_start: f64,
+    mat: Arc<Material>,
+}
+
+impl SpotLight {
+    pub fn new(position: Point3, target: Point3, intensity: Color, total_width: f64, falloff_start: f64) -> Self {
+        let total_width = f64::clamp(total_width, 0.0, 180.0);
+        let falloff_start = f64::clamp(falloff_start, 0.0, total_width);
+
+        Self {
+            position,
+            axis: (target - position).unit_vector(),
+            intensity,
+            cos_total_width: f64::cos(degrees_to_radians(total_width)),
+            cos_falloff_start: f64::cos(degrees_to_radians(falloff_start)),
+            mat: no_surface(),
+        }
+    }
+
+    // Fraction of the intensity emitted along a unit direction
+    fn falloff(&self, direction: &Vec3) -> f64 {
+        let cos_theta = dot(&self.axis, direction);
+        if cos_theta < self.cos_total_width {
+            return 0.0;
+        }
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+
+        let delta = (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        (delta * delta) * (delta * delta)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, p: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * self.falloff(&-direction) / distance_squared,
+            pdf: 1.0,
+        })
+    }
+
+    fn sample_le(&self) -> Option<LightEmission> {
+        let rec = HitRecord::new(self.position, Vec3::new(0.0, 0.0, 0.0), self.mat.clone(), 0.0, 0.0, 0.0, true);
+        let direction = sample_cone(&self.axis, self.cos_total_width);
+        Some(LightEmission {
+            rec,
+            direction,
+            radiance: self.intensity * self.falloff(&direction),
+            pdf_pos: 1.0,
+            pdf_dir: cone_pdf(self.cos_total_width),
+        })
+    }
+
+    fn le(&self, _rec: &HitRecord, direction: &Vec3) -> Color {
+        self.intensity * self.falloff(&direction.unit_vector())
+    }
+
+    fn pdf_le(&self, _rec: &HitRecord, direction: &Vec3) -> f64 {
+        if dot(&self.axis, &direction.unit_vector()) >= self.cos_total_width {
+            cone_pdf(self.cos_total_width)
+        } else {
+            0.0
+        }
+    }
+}
@@ -10,7 +10,13 @@ mod primitives;
 mod materials;
 mod bvh;
 mod textures;
+mod backgrounds;
+mod integrators;
+mod scene;
+mod media;
 mod external;
+mod spectrum;
+mod lights;
 
 use primitives::*;
 use materials::*;
@@ -21,6 +27,7 @@ use primitives::*;
 use bvh::*;
 use textures::*;
 use external::load_ply;
+use lights::*;
 
 fn main() {
     // Create the hittable list (world)
@@ -66,6 +73,15 @@ fn main() {
         1.0, 0.0, 0.0, 0.0)
     );
 
+    // Clear varnish reflecting 4% facing it, as a dielectric of index 1.5, and more at grazing angles
+    let material_varnished_white = Arc::new(Material::new(
+        white.clone(), 
+        spec.clone(), 
+        refrac.clone(),
+        None, 
+        0.96, 0.04, 0.0, 0.0)
+    );
+
     let material_box = Arc::new(Material::new(
         pourple.clone(), 
         spec.clone(), 
@@ -78,7 +94,7 @@ fn main() {
         white.clone(), 
         spec.clone(), 
         refrac.clone(),
-        Some(Color::new(10.0, 10.0, 10.0)), 
+        Some(Emission::new(Color::new(10.0, 10.0, 10.0))), 
         1.0, 0.0, 0.0, 0.0)
     );
 
@@ -119,7 +135,7 @@ fn main() {
         Point3::new(0.0, 0.0, 0.0),
         Vec3::new(555.0, 0.0, 0.0),
         Vec3::new(0.0, 0.0, 555.0),
-        material_matt_white.clone(),
+        material_varnished_white.clone(),
     )));
 
     // Ceiling
@@ -151,7 +167,7 @@ fn main() {
     let defocus_angle = 0.0;
     let focus_dist = (lookfrom - lookat).length();
 
-    let cam = Camera::new(
+    let mut cam = Camera::new(
         aspect_ratio,
         image_width,
         vfov,
@@ -162,6 +178,16 @@ fn main() {
         focus_dist,
     );
 
+    // Spot light shining on the box from the front right corner of the ceiling, lighting what
+    // the ceiling light leaves in the shade
+    cam.add_light(Arc::new(SpotLight::new(
+        Point3::new(100.0, 500.0, 100.0),
+        Point3::new(350.0, 165.0, 380.0),
+        Color::new(100000.0, 90000.0, 70000.0),
+        30.0,
+        20.0,
+    )));
+
     // Build BVH from the temporary hittable object list and create a new hittable object list with it
     let bvh_node: Arc<BVHNode> = Arc::new(BVHNode::new(world.objects.clone()));
     let mut new_world = HittableList::new();
@@ -0,0 +1,126 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::textures::{Texture, SolidColor};
+use crate::utils::random_double;
+
+// Luminous efficacy of the luminance weighted power, in lumens per watt
+const LUMENS_PER_WATT: f64 = 683.0;
+
+// How bright an emission is
+#[derive(Debug, Clone, Copy)]
+pub enum LightPower {
+    // Emitted radiance is the texture times this scale
+    Radiance(f64),
+    // Power emitted by all the surfaces using the emission together. The texture only gives the
+    // color, its luminance averaged over those surfaces is normalized away.
+    Watts(f64),
+    Lumens(f64),
+}
+
+// Light emitted by a surface, the same along every direction leaving it
+pub struct Emission {
+    texture: Arc<dyn Texture>,
+    power: LightPower,
+    two_sided: bool,            // Emits from the back face too
+    scale: AtomicU64,           // Bits of the f64 turning texture values into radiance
+}
+
+impl Emission {
+    // Two-sided emission of a constant radiance
+    pub fn new(radiance: Color) -> Arc<Emission> {
+        let texture = Arc::new(SolidColor::new(radiance)) as Arc<dyn Texture>;
+        Self::new_with_power(texture, LightPower::Radiance(1.0), true)
+    }
+
+    // Two-sided emission of the radiance given by a texture
+    pub fn new_from_texture(texture: Arc<dyn Texture>) -> Arc<Emission> {
+        Self::new_with_power(texture, LightPower::Radiance(1.0), true)
+    }
+
+    pub fn new_with_power(texture: Arc<dyn Texture>, power: LightPower, two_sided: bool) -> Arc<Emission> {
+        let scale = match power {
+            LightPower::Radiance(scale) => scale,
+            // Set once the surfaces emitting are known
+            LightPower::Watts(_) | LightPower::Lumens(_) => 0.0,
+        };
+
+        Arc::new(Emission { texture, power, two_sided, scale: AtomicU64::new(scale.to_bits()) })
+    }
+
+    // Emission colored as a black body at a temperature in Kelvin
+    pub fn new_blackbody(kelvin: f64, power: LightPower, two_sided: bool) -> Arc<Emission> {
+        let texture = Arc::new(SolidColor::new(blackbody(kelvin))) as Arc<dyn Texture>;
+        Self::new_with_power(texture, power, two_sided)
+    }
+
+    pub fn power(&self) -> LightPower {
+        self.power
+    }
+
+    pub fn is_two_sided(&self) -> bool {
+        self.two_sided
+    }
+
+    // Luminance of the texture at a point, to average it over the emitting surfaces
+    pub fn texture_luminance(&self, rec: &HitRecord) -> f64 {
+        luminance(&self.texture.value(rec.u, rec.v, &rec.p))
+    }
+
+    // Fix the scale of an emission given as a power, knowing the total area of the surfaces using
+    // it and the average luminance of its texture over them
+    pub fn set_emitting_area(&self, area: f64, average_luminance: f64) {
+        let power = match self.power {
+            LightPower::Radiance(_) => return,
+            LightPower::Watts(watts) => watts,
+            LightPower::Lumens(lumens) => lumens / LUMENS_PER_WATT,
+        };
+
+        // A surface of constant radiance L emits pi * L * area from each side
+        let sides = if self.two_sided { 2.0 } else { 1.0 };
+        let emitting = sides * PI * area * average_luminance;
+        let scale = if emitting > 0.0 { power / emitting } else { 0.0 };
+        self.scale.store(scale.to_bits(), Ordering::Relaxed);
+    }
+
+    // Radiance leaving the surface at a hit point towards the origin of the ray
+    pub fn radiance(&self, rec: &HitRecord) -> Color {
+        if !rec.front_face && !self.two_sided {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.texture.value(rec.u, rec.v, &rec.p) * f64::from_bits(self.scale.load(Ordering::Relaxed))
+    }
+
+    // Radiance leaving a point of the surface along direction, with rec holding its outward normal
+    pub fn radiance_towards(&self, rec: &HitRecord, direction: &Vec3) -> Color {
+        if !self.two_sided && dot(&rec.normal, direction) <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.texture.value(rec.u, rec.v, &rec.p) * f64::from_bits(self.scale.load(Ordering::Relaxed))
+    }
+
+    // Direction leaving the surface, cosine distributed around the outward normal (or either side
+    // of the surface for two-sided emissions)
+    pub fn sample_direction(&self, normal: &Vec3) -> Vec3 {
+        let side = if self.two_sided && random_double() < 0.5 { -*normal } else { *normal };
+        let direction = side + Vec3::random_unit_vector();
+        if direction.length_squared() < 1e-12 {
+            side
+        } else {
+            direction.unit_vector()
+        }
+    }
+
+    // Solid angle pdf of sample_direction
+    pub fn pdf_direction(&self, normal: &Vec3, direction: &Vec3) -> f64 {
+        let cos_theta = dot(normal, &direction.unit_vector());
+        if self.two_sided {
+            f64::abs(cos_theta) / (2.0 * PI)
+        } else {
+            f64::max(cos_theta, 0.0) / PI
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::scattering_function::ScatteringFunction;
+use crate::utils::random_double;
+
+// Share of the light reaching a layer that the layer takes, the rest goes on to the layers below
+#[derive(Debug, Clone, Copy)]
+pub enum LayerWeight {
+    Constant(f64),
+    // Fresnel reflectance of a dielectric interface with this index of refraction, as for the
+    // clear coat of plastics and varnished wood
+    Fresnel(f64),
+}
+
+pub struct Layer {
+    pub lobe: Arc<dyn ScatteringFunction>,
+    pub weight: LayerWeight,
+}
+
+impl Layer {
+    pub fn new(lobe: Arc<dyn ScatteringFunction>, weight: LayerWeight) -> Self {
+        Self { lobe, weight }
+    }
+
+    // Layer reflecting as a dielectric interface of index ior would, usually a specular lobe on top
+    pub fn coating(lobe: Arc<dyn ScatteringFunction>, ior: f64) -> Self {
+        Self::new(lobe, LayerWeight::Fresnel(ior))
+    }
+
+    // Layer taking all the light that reaches it
+    pub fn base(lobe: Arc<dyn ScatteringFunction>) -> Self {
+        Self::new(lobe, LayerWeight::Constant(1.0))
+    }
+}
+
+// Direction scattered by a layered BSDF, as returned by LayeredBsdf::sample
+pub struct BsdfSample {
+    pub scattered: Ray,
+    pub attenuation: Color,                 // BSDF times the cosine over the pdf
+    pub pdf: f64,                           // Solid angle pdf, zero after a specular event
+    pub delta: bool,                        // The direction comes from a specular event
+    pub lobe: Arc<dyn ScatteringFunction>,  // Lobe that scattered, for its adjoint scale and dispersion
+}
+
+// Stack of lobes from top to bottom. Every layer takes its share of the light reaching it and
+// passes the rest to the layers below, the light left after the last layer is absorbed. Shares
+// never add up to more than one, so the stack conserves energy if its lobes do, and coatings
+// reflect more at grazing angles as real ones do.
+//
+// The BSDF is the sum of the lobes weighted by their share. Evaluating it only accounts for the
+// non specular lobes, which are the ones light can be sampled for.
+pub struct LayeredBsdf {
+    layers: Vec<Layer>,
+}
+
+impl LayeredBsdf {
+    pub fn new(layers: Vec<Layer>) -> Arc<LayeredBsdf> {
+        Arc::new(LayeredBsdf { layers })
+    }
+
+    // Lobe under a clear coat of index ior, such as plastic over a diffuse lobe
+    pub fn new_coated(base: Arc<dyn ScatteringFunction>, coat: Arc<dyn ScatteringFunction>, ior: f64) -> Arc<LayeredBsdf> {
+        Self::new(vec![Layer::coating(coat, ior), Layer::base(base)])
+    }
+
+    // Share of the light arriving along r_in taken by every layer
+    fn shares<'a>(&'a self, r_in: &Ray, rec: &HitRecord) -> impl Iterator<Item = (&'a Layer, f64)> + 'a {
+        let cos_theta = f64::min(f64::abs(dot(&r_in.dir.unit_vector(), &rec.normal)), 1.0);
+        let front_face = rec.front_face;
+
+        self.layers.iter().scan(1.0, move |remaining: &mut f64, layer| {
+            let taken = match layer.weight {
+                LayerWeight::Constant(weight) => weight.clamp(0.0, 1.0),
+                LayerWeight::Fresnel(ior) => fresnel(cos_theta, if front_face { 1.0 / ior } else { ior }),
+            };
+            let share = *remaining * taken;
+            *remaining -= share;
+            Some((layer, share))
+        })
+    }
+
+    // Pick a layer in proportion to its share and scatter with its lobe. None if the light is absorbed.
+    pub fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        let total: f64 = self.shares(r_in, rec).map(|(_, share)| share).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut choice = random_double() * total;
+        let mut chosen = None;
+        for (layer, share) in self.shares(r_in, rec) {
+            if share <= 0.0 {
+                continue;
+            }
+            chosen = Some(layer);
+            if choice < share {
+                break;
+            }
+            choice -= share;
+        }
+        let lobe = &chosen?.lobe;
+
+        let mut scattered = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+        let mut attenuation = Color::new(0.0, 0.0, 0.0);
+        if !lobe.scatter(r_in, rec, &mut attenuation, &mut scattered) {
+            return None;
+        }
+
+        // The lobe sampled with probability share / total
+        if lobe.is_delta(r_in, rec, &scattered) {
+            return Some(BsdfSample { scattered, attenuation: attenuation * total, pdf: 0.0, delta: true, lobe: lobe.clone() });
+        }
+
+        // Other lobes with a density at the same direction could have chosen it, the estimate uses all of them
+        let non_specular = self.shares(r_in, rec).filter(|(layer, share)| *share > 0.0 && !layer.lobe.is_delta(r_in, rec, &scattered)).count();
+        let pdf = self.pdf(r_in, rec, &scattered);
+        if non_specular > 1 && pdf > 0.0 {
+            attenuation = self.eval(r_in, rec, &scattered) / pdf;
+        } else {
+            attenuation = attenuation * total;
+        }
+
+        Some(BsdfSample { scattered, attenuation, pdf, delta: false, lobe: lobe.clone() })
+    }
+
+    // eval for light carried from the lights, with every lobe scaled by its adjoint_scale
+    pub fn eval_adjoint(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        self.shares(r_in, rec)
+            .filter(|(layer, share)| *share > 0.0 && !layer.lobe.is_delta(r_in, rec, scattered))
+            .fold(Color::new(0.0, 0.0, 0.0), |f, (layer, share)| {
+                f + share * layer.lobe.adjoint_scale(r_in, rec, scattered) * layer.lobe.eval(r_in, rec, scattered)
+            })
+    }
+}
+
+impl ScatteringFunction for LayeredBsdf {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        match self.sample(r_in, rec) {
+            Some(sample) => {
+                *attenuation = sample.attenuation;
+                *scattered = sample.scattered;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        self.shares(r_in, rec)
+            .filter(|(layer, share)| *share > 0.0 && !layer.lobe.is_delta(r_in, rec, scattered))
+            .fold(Color::new(0.0, 0.0, 0.0), |f, (layer, share)| f + share * layer.lobe.eval(r_in, rec, scattered))
+    }
+
+    // Density of sample choosing the direction through any of the lobes not reaching it by a delta event
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let total: f64 = self.shares(r_in, rec).map(|(_, share)| share).sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+
+        self.shares(r_in, rec)
+            .filter(|(layer, share)| *share > 0.0 && !layer.lobe.is_delta(r_in, rec, scattered))
+            .map(|(layer, share)| share / total * layer.lobe.pdf(r_in, rec, scattered))
+            .sum()
+    }
+
+    // Whole stack specular, there is nothing to sample light for
+    fn is_specular(&self) -> bool {
+        self.layers.iter().all(|layer| layer.lobe.is_specular())
+    }
+
+    // Only specular events of the lobes taking a share of the light lead to scattered
+    fn is_delta(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> bool {
+        self.shares(r_in, rec).all(|(layer, share)| share <= 0.0 || layer.lobe.is_delta(r_in, rec, scattered))
+    }
+
+    // Rays of the specular events of every lobe, weighted by their share. They carry the
+    // wavelengths of r_in, only the hero one after dispersive lobes.
+    fn specular_directions(&self, r_in: &Ray, rec: &HitRecord) -> Vec<(Color, Ray)> {
+        let mut directions = Vec::new();
+        for (layer, share) in self.shares(r_in, rec) {
+            if share <= 0.0 {
+                continue;
+            }
+
+            for (attenuation, mut scattered) in layer.lobe.specular_directions(r_in, rec) {
+                scattered.wavelengths = r_in.wavelengths;
+                if layer.lobe.is_dispersive() {
+                    scattered.wavelengths.terminate_secondary();
+                }
+                directions.push((share * attenuation, scattered));
+            }
+        }
+        directions
+    }
+}
+
+// Schlick approximation of the Fresnel reflectance, with refraction_ratio the index of the side
+// light comes from over the other one. Light that cannot leave is totally reflected.
+fn fresnel(cos_theta: f64, refraction_ratio: f64) -> f64 {
+    let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+    if refraction_ratio * sin_theta > 1.0 {
+        return 1.0;
+    }
+
+    let mut r0 = (1.0 - refraction_ratio) / (1.0 + refraction_ratio);
+    r0 = r0 * r0;
+    r0 + (1.0 - r0) * f64::powi(1.0 - cos_theta, 5)
+}
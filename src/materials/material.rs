@@ -1,47 +1,143 @@
 use std::sync::Arc;
 use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::materials::{Emission, LayeredBsdf, Layer, LayerWeight};
 use crate::scattering_function::*;
 
 #[derive(Clone)]
 pub struct Material {
-    pub diffuse: Arc<dyn ScatteringFunction>,
-    pub specular: Arc<dyn ScatteringFunction>,
-    pub refractive: Arc<dyn ScatteringFunction>,
-    pub emit: Option<Color>,  // Optional emission color for emissive materials
-    pub kd: f64,  // Diffuse coefficient
-    pub ks: f64,  // Specular coefficient
-    pub kt: f64,  // Transmission/refractive coefficient
-    pub absorption: f64,  // Absorption coefficient
+    pub bsdf: Arc<LayeredBsdf>,       // How light arriving at the surface is scattered
+    pub emit: Option<Arc<Emission>>,  // Optional emission for emissive materials
 }
 
 impl Material {
+    // Diffuse, specular and refractive lobes sharing the light in proportion to the kd, ks and kt
+    // coefficients, with the absorption coefficient the share absorbed. The specular lobe coats
+    // the others: ks is its share at normal incidence, growing towards grazing angles as for a
+    // dielectric interface reflecting that much, and the other lobes share what it lets through.
     pub fn new(
         diffuse: Arc<dyn ScatteringFunction>,
         specular: Arc<dyn ScatteringFunction>,
         refractive: Arc<dyn ScatteringFunction>,
-        emit: Option<Color>,
-        mut kd: f64,
-        mut ks: f64,
-        mut kt: f64,
-        mut absorption: f64,
+        emit: Option<Arc<Emission>>,
+        kd: f64,
+        ks: f64,
+        kt: f64,
+        absorption: f64,
     ) -> Self {
         let sum = kd + ks + kt + absorption;
-        if sum > 0.0 {
-            kd /= sum;
-            ks /= sum;
-            kt /= sum;
-            absorption /= sum;
+        let mut layers = Vec::new();
+        if sum <= 0.0 {
+            return Self::new_layered(LayeredBsdf::new(layers), emit);
         }
 
-        Material {
-            diffuse,
-            specular,
-            refractive,
-            emit,
-            kd,
-            ks,
-            kt,
-            absorption,
+        let reflectance = ks / sum;
+        if reflectance >= 1.0 {
+            layers.push(Layer::base(specular));
+        } else if reflectance > 0.0 {
+            layers.push(Layer::coating(specular, coating_ior(reflectance)));
+        }
+
+        // Under the coating, each layer takes its share of what the layers above left
+        let below = kd + kt + absorption;
+        let mut remaining = 1.0;
+        for (lobe, k) in [(diffuse, kd), (refractive, kt)] {
+            if k <= 0.0 {
+                continue;
+            }
+            let share = k / below;
+            layers.push(Layer::new(lobe, LayerWeight::Constant(f64::min(share / remaining, 1.0))));
+            remaining -= share;
+        }
+
+        Self::new_layered(LayeredBsdf::new(layers), emit)
+    }
+
+    // Material scattering all the light with a single lobe, such as Principled
+    pub fn new_from_lobe(lobe: Arc<dyn ScatteringFunction>, emit: Option<Arc<Emission>>) -> Self {
+        Self::new_layered(LayeredBsdf::new(vec![Layer::base(lobe)]), emit)
+    }
+
+    pub fn new_layered(bsdf: Arc<LayeredBsdf>, emit: Option<Arc<Emission>>) -> Self {
+        Material { bsdf, emit }
+    }
+
+    // Radiance emitted at a hit point towards the origin of the ray, black if the material does not emit
+    pub fn emitted(&self, rec: &HitRecord) -> Color {
+        match &self.emit {
+            Some(emission) => emission.radiance(rec),
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+// Index of refraction of a dielectric interface reflecting the given share of the light at normal incidence
+fn coating_ior(reflectance: f64) -> f64 {
+    let r = reflectance.sqrt();
+    (1.0 + r) / (1.0 - r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    // White diffuse lobe under a clear coat reflecting ks at normal incidence
+    fn coated(kd: f64, ks: f64, absorption: f64) -> Material {
+        let white = Color::new(1.0, 1.0, 1.0);
+        Material::new(Lambertian::new(white), Specular::new(), Refractive::new(1.5), None, kd, ks, 0.0, absorption)
+    }
+
+    // Share of the light arriving with the given cosine reflected by the specular events, and by
+    // the others integrated over the hemisphere
+    fn reflected(material: &Material, cos_in: f64) -> (f64, f64) {
+        let rec = HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Arc::new(material.clone()), 1.0, 0.0, 0.0, true);
+        let sin_in = f64::sqrt(1.0 - cos_in * cos_in);
+        let r_in = Ray::new(Point3::new(-sin_in, cos_in, 0.0), Vec3::new(sin_in, -cos_in, 0.0));
+
+        let specular: f64 = material.bsdf.specular_directions(&r_in, &rec).iter().map(|(attenuation, _)| attenuation.x).sum();
+
+        let (steps_theta, steps_phi) = (200, 100);
+        let dtheta = PI / 2.0 / steps_theta as f64;
+        let dphi = 2.0 * PI / steps_phi as f64;
+        let mut diffuse = 0.0;
+        for i in 0..steps_theta {
+            let theta = (i as f64 + 0.5) * dtheta;
+            for j in 0..steps_phi {
+                let phi = (j as f64 + 0.5) * dphi;
+                let direction = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                diffuse += material.bsdf.eval(&r_in, &rec, &Ray::new(rec.p, direction)).x * theta.sin() * dtheta * dphi;
+            }
+        }
+        (specular, diffuse)
+    }
+
+    #[test]
+    fn coating_reflects_more_at_grazing_angles() {
+        let material = coated(0.8, 0.2, 0.0);
+        let (normal, _) = reflected(&material, 1.0);
+        assert!((normal - 0.2).abs() < 1e-9, "{normal}");
+
+        let mut previous = normal;
+        for cos_in in [0.8, 0.5, 0.3, 0.1, 0.02] {
+            let (specular, _) = reflected(&material, cos_in);
+            assert!(specular > previous, "cos {cos_in}: {specular} <= {previous}");
+            previous = specular;
+        }
+        assert!(previous > 0.8);
+    }
+
+    #[test]
+    fn shares_conserve_energy() {
+        for cos_in in [1.0, 0.7, 0.3, 0.05] {
+            // Nothing absorbed, white lobes reflect everything
+            let (specular, diffuse) = reflected(&coated(0.8, 0.2, 0.0), cos_in);
+            assert!((specular + diffuse - 1.0).abs() < 1e-3, "cos {cos_in}: {specular} + {diffuse}");
+
+            // Under the coating, kd and the absorption share what it lets through
+            let (specular, diffuse) = reflected(&coated(0.6, 0.2, 0.2), cos_in);
+            let expected = specular + (1.0 - specular) * 0.6 / 0.8;
+            assert!((specular + diffuse - expected).abs() < 1e-3, "cos {cos_in}: {specular} + {diffuse} != {expected}");
         }
     }
 }
@@ -2,5 +2,11 @@
 pub mod material;
 pub use self::material::Material;
 
+pub mod layered_bsdf;
+pub use self::layered_bsdf::{LayeredBsdf, Layer, LayerWeight, BsdfSample};
+
+pub mod emission;
+pub use self::emission::{Emission, LightPower};
+
 pub mod scattering_function;
 pub use self::scattering_function::*;
\ No newline at end of file
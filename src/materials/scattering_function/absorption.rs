@@ -0,0 +1,35 @@
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+
+// Beer–Lambert absorption inside a dielectric, Refractive or RoughDielectric. Light crossing a
+// distance d of the medium is left with exp(-σ d) of each channel, so thick glass is darker and
+// more saturated than thin glass.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Absorption {
+    sigma: Color,   // Absorption coefficient per unit distance
+}
+
+impl Absorption {
+    // Medium leaving the transmittance color of the light after crossing the given distance
+    pub fn new(transmittance: Color, distance: f64) -> Self {
+        if distance <= 0.0 {
+            return Self::default();
+        }
+        let sigma = |t: f64| -f64::ln(t.clamp(1e-6, 1.0)) / distance;
+        Self { sigma: Color::new(sigma(transmittance.x), sigma(transmittance.y), sigma(transmittance.z)) }
+    }
+
+    // Share of the light left to a ray reaching rec. Rays hitting a back face come from inside
+    // the medium, and crossed it all the way from their origin.
+    pub fn transmittance(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        if rec.front_face {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+        let distance = rec.t * r_in.dir.length();
+        Color::new(
+            f64::exp(-self.sigma.x * distance),
+            f64::exp(-self.sigma.y * distance),
+            f64::exp(-self.sigma.z * distance),
+        )
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::spectrum::ComplexIor;
+use super::thin_film::ThinFilm;
+use super::microfacet::{Frame, TrowbridgeReitz, reflection_normal};
+use super::scattering_function::ScatteringFunction;
+
+// Rough metal made of GGX microfacets, each a perfect mirror reflecting as the Fresnel equations
+// of the complex index of refraction say. Directions are sampled from the microfacets visible
+// from the incoming ray. With no roughness it is a perfect mirror. A thin film over the metal
+// tints the reflection with interference colors, as on heated steel.
+pub struct Conductor {
+    ior: ComplexIor,
+    distribution: TrowbridgeReitz,
+    film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(ior: ComplexIor, roughness: f64) -> Arc<dyn ScatteringFunction> {
+        Arc::new(Conductor { ior, distribution: TrowbridgeReitz::new(roughness), film: None }) as Arc<dyn ScatteringFunction>
+    }
+
+    pub fn new_with_film(ior: ComplexIor, roughness: f64, film: ThinFilm) -> Arc<dyn ScatteringFunction> {
+        Arc::new(Conductor { ior, distribution: TrowbridgeReitz::new(roughness), film: Some(film) }) as Arc<dyn ScatteringFunction>
+    }
+
+    pub fn new_gold(roughness: f64) -> Arc<dyn ScatteringFunction> {
+        Self::new(ComplexIor::GOLD, roughness)
+    }
+
+    pub fn new_silver(roughness: f64) -> Arc<dyn ScatteringFunction> {
+        Self::new(ComplexIor::SILVER, roughness)
+    }
+
+    pub fn new_copper(roughness: f64) -> Arc<dyn ScatteringFunction> {
+        Self::new(ComplexIor::COPPER, roughness)
+    }
+
+    pub fn new_aluminium(roughness: f64) -> Arc<dyn ScatteringFunction> {
+        Self::new(ComplexIor::ALUMINIUM, roughness)
+    }
+
+    pub fn new_chrome(roughness: f64) -> Arc<dyn ScatteringFunction> {
+        Self::new(ComplexIor::CHROME, roughness)
+    }
+
+    fn reflectance(&self, rec: &HitRecord, cos_theta: f64) -> Color {
+        match &self.film {
+            Some(film) => film.reflectance(rec, cos_theta, 1.0, &self.ior),
+            None => self.ior.reflectance(cos_theta),
+        }
+    }
+}
+
+impl ScatteringFunction for Conductor {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        if self.distribution.is_smooth() {
+            *scattered = Ray::new(rec.p, reflect(&r_in.dir.unit_vector(), &rec.normal));
+            *attenuation = self.reflectance(rec, wo.z);
+            return true;
+        }
+
+        // Mirror reflection on a visible microfacet, weighted by the part of the microfacets
+        // visible from wo that the reflected direction also sees
+        let wm = self.distribution.sample_wm(&wo);
+        let wi = reflect(&-wo, &wm);
+        if wi.z <= 0.0 {
+            return false;
+        }
+
+        *scattered = Ray::new(rec.p, frame.from_local(&wi));
+        *attenuation = self.reflectance(rec, dot(&wo, &wm)) * self.distribution.g(&wo, &wi) / self.distribution.g1(&wo);
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        if self.distribution.is_smooth() {
+            return black;
+        }
+
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        let wi = frame.to_local(&scattered.dir.unit_vector());
+        match reflection_normal(&wo, &wi) {
+            Some(wm) => self.reflectance(rec, dot(&wo, &wm)) * self.distribution.eval_reflection(&wo, &wi, &wm),
+            None => black,
+        }
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        let wi = frame.to_local(&scattered.dir.unit_vector());
+        match reflection_normal(&wo, &wi) {
+            Some(wm) => self.distribution.pdf_reflection(&wo, &wm),
+            None => 0.0,
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth()
+    }
+
+    fn specular_directions(&self, r_in: &Ray, rec: &HitRecord) -> Vec<(Color, Ray)> {
+        if !self.distribution.is_smooth() {
+            return Vec::new();
+        }
+        let cos_theta = f64::abs(dot(&r_in.dir.unit_vector(), &rec.normal));
+        let direction = reflect(&r_in.dir.unit_vector(), &rec.normal);
+        vec![(self.reflectance(rec, cos_theta), Ray::new(rec.p, direction))]
+    }
+}
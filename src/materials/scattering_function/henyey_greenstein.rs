@@ -0,0 +1,68 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::textures::{Texture, SolidColor};
+use crate::utils::random_double;
+
+use super::ScatteringFunction;
+
+// Henyey-Greenstein phase function. The asymmetry g goes from -1 (light bounces back) to 1
+// (light keeps its direction), with 0 being isotropic. Smoke and clouds scatter forward.
+pub struct HenyeyGreenstein {
+    texture: Arc<dyn Texture>,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Arc<dyn ScatteringFunction> {
+        let solid_color_texture = Arc::new(SolidColor::new(albedo)) as Arc<dyn Texture>;
+        Self::new_from_texture(solid_color_texture, g)
+    }
+
+    pub fn new_from_texture(texture: Arc<dyn Texture>, g: f64) -> Arc<dyn ScatteringFunction> {
+        let g = g.clamp(-0.999, 0.999);
+        Arc::new(HenyeyGreenstein { texture, g }) as Arc<dyn ScatteringFunction>
+    }
+
+    // Density over the sphere of turning by an angle with the given cosine
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let denominator = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+        (1.0 - self.g * self.g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+}
+
+impl ScatteringFunction for HenyeyGreenstein {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        let g = self.g;
+        let xi = random_double();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+        let phi = 2.0 * PI * random_double();
+
+        // Basis around the direction of travel
+        let w = r_in.dir.unit_vector();
+        let a = if w.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let v = cross(&w, &a).unit_vector();
+        let u = cross(&w, &v);
+        let direction = sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * w;
+
+        *scattered = Ray::new(rec.p, direction);
+        *attenuation = self.texture.value(rec.u, rec.v, &rec.p);
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        self.texture.value(rec.u, rec.v, &rec.p) * self.pdf(r_in, rec, scattered)
+    }
+
+    // Sampling follows the phase function exactly
+    fn pdf(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.phase(dot(&r_in.dir.unit_vector(), &scattered.dir.unit_vector()))
+    }
+}
@@ -0,0 +1,40 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::textures::{Texture, SolidColor};
+
+use super::ScatteringFunction;
+
+// Phase function of participating media scattering equally in every direction
+pub struct Isotropic {
+    texture: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Arc<dyn ScatteringFunction> {
+        let solid_color_texture = Arc::new(SolidColor::new(albedo)) as Arc<dyn Texture>;
+        Arc::new(Isotropic { texture: solid_color_texture }) as Arc<dyn ScatteringFunction>
+    }
+
+    pub fn new_from_texture(texture: Arc<dyn Texture>) -> Arc<dyn ScatteringFunction> {
+        Arc::new(Isotropic { texture }) as Arc<dyn ScatteringFunction>
+    }
+}
+
+impl ScatteringFunction for Isotropic {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        *scattered = Ray::new(rec.p, Vec3::random_unit_vector());
+        *attenuation = self.texture.value(rec.u, rec.v, &rec.p);
+        true
+    }
+
+    // There is no surface, so no cosine term
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, _scattered: &Ray) -> Color {
+        self.texture.value(rec.u, rec.v, &rec.p) / (4.0 * PI)
+    }
+
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
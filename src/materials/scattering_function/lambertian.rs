@@ -1,3 +1,4 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 use crate::primitives::*;
 use crate::hittable::HitRecord;
@@ -29,13 +30,25 @@ impl ScatteringFunction for Lambertian {
             scatter_direction = rec.normal;
         }
 
-        *scattered = Ray {
-            orig: rec.p,
-            dir: scatter_direction,
-        };
+        *scattered = Ray::new(rec.p, scatter_direction);
         *attenuation = self.texture.value(rec.u, rec.v, &rec.p);
         true
     }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let cos_theta = dot(&rec.normal, &scattered.dir.unit_vector());
+        if cos_theta <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        self.texture.value(rec.u, rec.v, &rec.p) * (cos_theta / PI)
+    }
+
+    // Scatter picks cosine distributed directions
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = dot(&rec.normal, &scattered.dir.unit_vector());
+        f64::max(cos_theta, 0.0) / PI
+    }
 }
 
 impl Default for Lambertian {
@@ -0,0 +1,148 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::textures::{Texture, SolidColor};
+use super::scattering_function::ScatteringFunction;
+
+// Reflection off a metal tinted by its albedo, the reflectance at normal incidence, which turns
+// white at grazing angles. Fuzz (from zero to one) roughens the surface moving the reflected
+// direction to a random point of a sphere of that radius around it. Directions pushed under the
+// surface are absorbed.
+pub struct Metal {
+    texture: Arc<dyn Texture>,
+    fuzz: f64,
+}
+
+impl Metal {
+    pub fn new(albedo: Color, fuzz: f64) -> Arc<dyn ScatteringFunction> {
+        let solid_color_texture = Arc::new(SolidColor::new(albedo)) as Arc<dyn Texture>;
+        Self::new_from_texture(solid_color_texture, fuzz)
+    }
+
+    pub fn new_from_texture(texture: Arc<dyn Texture>, fuzz: f64) -> Arc<dyn ScatteringFunction> {
+        Arc::new(Metal { texture, fuzz: fuzz.clamp(0.0, 1.0) }) as Arc<dyn ScatteringFunction>
+    }
+
+    // Schlick's approximation with the albedo as the reflectance at normal incidence
+    fn reflectance(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        let albedo = self.texture.value(rec.u, rec.v, &rec.p);
+        let cos_theta = f64::min(f64::abs(dot(&r_in.dir.unit_vector(), &rec.normal)), 1.0);
+        let grazing = f64::powi(1.0 - cos_theta, 5);
+        albedo + (Color::new(1.0, 1.0, 1.0) - albedo) * grazing
+    }
+}
+
+impl ScatteringFunction for Metal {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        let reflected = reflect(&r_in.dir.unit_vector(), &rec.normal);
+        let direction = reflected + self.fuzz * Vec3::random_unit_vector();
+
+        *scattered = Ray::new(rec.p, direction);
+        *attenuation = self.reflectance(r_in, rec);
+        dot(&direction, &rec.normal) > 0.0
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        if dot(&scattered.dir, &rec.normal) <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.reflectance(r_in, rec) * self.pdf(r_in, rec, scattered)
+    }
+
+    // The direction crosses the fuzz sphere at the distances t solving t² - 2 t cos + 1 - fuzz² = 0,
+    // with cos between the direction and the reflection. Each crossing contributes the density of
+    // the sphere, 1 / (4π fuzz²), turned into solid angle by t² / |cosine with the sphere normal|.
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        if self.fuzz <= 0.0 {
+            return 0.0;
+        }
+
+        let reflected = reflect(&r_in.dir.unit_vector(), &rec.normal);
+        let cos_theta = dot(&scattered.dir.unit_vector(), &reflected);
+        let discriminant = cos_theta * cos_theta - (1.0 - self.fuzz * self.fuzz);
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+
+        let root = discriminant.sqrt();
+        let crossings: f64 = [cos_theta - root, cos_theta + root]
+            .iter()
+            .filter(|t| **t > 0.0)
+            .map(|t| t * t)
+            .sum();
+        crossings / (4.0 * PI * self.fuzz * root)
+    }
+
+    // Without fuzz there is a single reflected direction
+    fn is_specular(&self) -> bool {
+        self.fuzz <= 0.0
+    }
+
+    fn specular_directions(&self, r_in: &Ray, rec: &HitRecord) -> Vec<(Color, Ray)> {
+        if self.fuzz > 0.0 {
+            return Vec::new();
+        }
+        let direction = reflect(&r_in.dir.unit_vector(), &rec.normal);
+        vec![(self.reflectance(r_in, rec), Ray::new(rec.p, direction))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use crate::materials::scattering_function::{Lambertian, microfacet::Frame};
+
+    fn hit() -> HitRecord {
+        let material = Arc::new(Material::new_from_lobe(Lambertian::new(Color::new(0.5, 0.5, 0.5)), None));
+        HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), material, 1.0, 0.0, 0.0, true)
+    }
+
+    // Integral of the pdf over the directions above the surface, in coordinates around the
+    // reflected direction with cos = rim + s² so that the density is smooth at the rim of the cone
+    fn pdf_above(metal: &dyn ScatteringFunction, r_in: &Ray, rec: &HitRecord, fuzz: f64) -> f64 {
+        let frame = Frame::new(&reflect(&r_in.dir.unit_vector(), &rec.normal));
+        let rim = f64::sqrt(1.0 - fuzz * fuzz);
+        let s_max = f64::sqrt(1.0 - rim);
+        let (steps_s, steps_phi) = (1000, 720);
+        let ds = s_max / steps_s as f64;
+        let dphi = 2.0 * PI / steps_phi as f64;
+
+        let mut total = 0.0;
+        for i in 0..steps_s {
+            let s = (i as f64 + 0.5) * ds;
+            let cos_theta = rim + s * s;
+            let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+            for j in 0..steps_phi {
+                let phi = (j as f64 + 0.5) * dphi;
+                let direction = frame.from_local(&Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta));
+                if dot(&direction, &rec.normal) <= 0.0 {
+                    continue;
+                }
+                total += metal.pdf(r_in, rec, &Ray::new(rec.p, direction)) * 2.0 * s * ds * dphi;
+            }
+        }
+        total
+    }
+
+    // Fuzz adds a uniform point of a sphere of radius fuzz to the unit reflected direction, which
+    // stays above the surface for the cap of the sphere above the plane at -cos_r / fuzz
+    fn kept_fraction(cos_reflected: f64, fuzz: f64) -> f64 {
+        (1.0 + f64::min(cos_reflected / fuzz, 1.0)) / 2.0
+    }
+
+    #[test]
+    fn pdf_integrates_to_the_light_not_absorbed() {
+        let rec = hit();
+        for (cos_in, fuzz) in [(0.7, 0.5), (0.3, 0.8), (0.1, 1.0), (0.9, 0.2)] {
+            let sin_in = f64::sqrt(1.0 - cos_in * cos_in);
+            let r_in = Ray::new(Point3::new(-sin_in, cos_in, 0.0), Vec3::new(sin_in, -cos_in, 0.0));
+            let metal = Metal::new(Color::new(0.9, 0.9, 0.9), fuzz);
+
+            let integral = pdf_above(metal.as_ref(), &r_in, &rec, fuzz);
+            let expected = kept_fraction(cos_in, fuzz);
+            assert!((integral - expected).abs() < 1e-3, "cos {cos_in} fuzz {fuzz}: {integral} != {expected}");
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::f64::consts::PI;
+use crate::primitives::*;
+use crate::utils::random_double;
+
+// Orthonormal basis around a normal, for working with directions where the normal is z
+pub struct Frame {
+    s: Vec3,
+    t: Vec3,
+    n: Vec3,
+}
+
+impl Frame {
+    pub fn new(normal: &Vec3) -> Self {
+        let n = normal.unit_vector();
+        let a = if f64::abs(n.x) > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let t = n.cross(&a).unit_vector();
+        let s = t.cross(&n);
+        Self { s, t, n }
+    }
+
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(dot(v, &self.s), dot(v, &self.t), dot(v, &self.n))
+    }
+
+    pub fn from_local(&self, v: &Vec3) -> Vec3 {
+        v.x * self.s + v.y * self.t + v.z * self.n
+    }
+}
+
+// GGX (Trowbridge-Reitz) distribution of microfacet normals, in the local frame of the surface.
+// Roughness goes from zero, a perfectly smooth surface, to one, and alpha is its square.
+#[derive(Debug, Clone, Copy)]
+pub struct TrowbridgeReitz {
+    alpha: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(roughness: f64) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Self { alpha: roughness * roughness }
+    }
+
+    // Too smooth to be told apart from a perfect mirror, and to be sampled without overflowing
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < 1e-3
+    }
+
+    // Density of microfacets with normal wm per unit area of the surface and solid angle
+    pub fn d(&self, wm: &Vec3) -> f64 {
+        if wm.z <= 0.0 {
+            return 0.0;
+        }
+        let alpha2 = self.alpha * self.alpha;
+        let cos2 = wm.z * wm.z;
+        let e = (wm.x * wm.x + wm.y * wm.y) / (alpha2 * cos2);
+        1.0 / (PI * alpha2 * cos2 * cos2 * (1.0 + e) * (1.0 + e))
+    }
+
+    // Smith's auxiliary function, the shadowed area of the microfacets seen from w over the visible one
+    fn lambda(&self, w: &Vec3) -> f64 {
+        if w.z == 0.0 {
+            return INFINITY_LAMBDA;
+        }
+        let alpha2_tan2 = self.alpha * self.alpha * (w.x * w.x + w.y * w.y) / (w.z * w.z);
+        (f64::sqrt(1.0 + alpha2_tan2) - 1.0) / 2.0
+    }
+
+    // Fraction of the microfacets visible from w
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Fraction of the microfacets visible from both wo and wi
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Density of the normals visible from w, the distribution sample_wm follows. Microfacets
+    // facing away from w are hidden.
+    pub fn visible_d(&self, w: &Vec3, wm: &Vec3) -> f64 {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        let w = if w.z < 0.0 { -*w } else { *w };
+        self.g1(&w) / w.z * self.d(wm) * f64::max(0.0, dot(&w, wm))
+    }
+
+    // Normal of a microfacet visible from w, following Heitz's sampling of the visible normals:
+    // the view is stretched to make the surface isotropic with unit roughness, where the visible
+    // normals are found projecting a point of a disk onto the hemisphere around w
+    pub fn sample_wm(&self, w: &Vec3) -> Vec3 {
+        let w = if w.z < 0.0 { -*w } else { *w };
+        let wh = Vec3::new(self.alpha * w.x, self.alpha * w.y, w.z).unit_vector();
+
+        let length_squared = wh.x * wh.x + wh.y * wh.y;
+        let t1 = if length_squared > 0.0 {
+            Vec3::new(-wh.y, wh.x, 0.0) / length_squared.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = wh.cross(&t1);
+
+        let r = f64::sqrt(random_double());
+        let phi = 2.0 * PI * random_double();
+        let p1 = r * f64::cos(phi);
+        let s = 0.5 * (1.0 + wh.z);
+        let p2 = (1.0 - s) * f64::sqrt(1.0 - p1 * p1) + s * r * f64::sin(phi);
+        let pz = f64::sqrt(f64::max(0.0, 1.0 - p1 * p1 - p2 * p2));
+
+        let nh = p1 * t1 + p2 * t2 + pz * wh;
+        Vec3::new(self.alpha * nh.x, self.alpha * nh.y, f64::max(1e-6, nh.z)).unit_vector()
+    }
+
+    // Mirror reflection of wo on a visible microfacet, None if it ends under the surface
+    pub fn sample_reflection(&self, wo: &Vec3) -> Option<Vec3> {
+        let wm = self.sample_wm(wo);
+        let wi = reflect(&-*wo, &wm);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        Some(wi)
+    }
+
+    // D G / (4 cos_o), the BSDF of microfacet reflection times the cosine at wi, without the
+    // Fresnel term of the microfacet normal wm
+    pub fn eval_reflection(&self, wo: &Vec3, wi: &Vec3, wm: &Vec3) -> f64 {
+        self.d(wm) * self.g(wo, wi) / (4.0 * wo.z)
+    }
+
+    // Density of the visible normal, over the 4 |wo·wm| of turning it into a reflected direction
+    pub fn pdf_reflection(&self, wo: &Vec3, wm: &Vec3) -> f64 {
+        self.visible_d(wo, wm) / (4.0 * f64::abs(dot(wo, wm)))
+    }
+
+    // Reflection or refraction of wo on a visible microfacet of a dielectric interface, chosen by
+    // the Fresnel reflectance of the microfacet. None if the direction ends on the wrong side.
+    pub fn sample_dielectric(&self, wo: &Vec3, eta: f64) -> Option<Vec3> {
+        let wm = self.sample_wm(wo);
+        let cos_theta_o = dot(wo, &wm);
+
+        if random_double() < fresnel_dielectric(cos_theta_o, eta) {
+            let wi = reflect(&-*wo, &wm);
+            return if wi.z > 0.0 { Some(wi) } else { None };
+        }
+
+        let sin2_theta_t = (1.0 - cos_theta_o * cos_theta_o) / (eta * eta);
+        let cos_theta_t = f64::sqrt(f64::max(0.0, 1.0 - sin2_theta_t));
+        let wi = -*wo / eta + (cos_theta_o / eta - cos_theta_t) * wm;
+        if wi.z < 0.0 { Some(wi) } else { None }
+    }
+
+    // BSDF of a rough dielectric interface times the cosine at wi (which cancels out), without
+    // rescaling the radiance crossing it
+    pub fn eval_dielectric(&self, wo: &Vec3, wi: &Vec3, eta: f64) -> f64 {
+        let wm = match dielectric_normal(wo, wi, eta) {
+            Some(wm) => wm,
+            None => return 0.0,
+        };
+        let fresnel = fresnel_dielectric(dot(wo, &wm), eta);
+
+        if wi.z > 0.0 {
+            self.eval_reflection(wo, wi, &wm) * fresnel
+        } else {
+            let denom = dot(wi, &wm) + dot(wo, &wm) / eta;
+            self.d(&wm) * (1.0 - fresnel) * self.g(wo, wi) * f64::abs(dot(wi, &wm) * dot(wo, &wm)) / (wo.z * denom * denom)
+        }
+    }
+
+    // Density of the visible normal, times the Jacobian of turning it into wi and the
+    // probability of reflecting or refracting
+    pub fn pdf_dielectric(&self, wo: &Vec3, wi: &Vec3, eta: f64) -> f64 {
+        let wm = match dielectric_normal(wo, wi, eta) {
+            Some(wm) => wm,
+            None => return 0.0,
+        };
+        let fresnel = fresnel_dielectric(dot(wo, &wm), eta);
+
+        if wi.z > 0.0 {
+            self.pdf_reflection(wo, &wm) * fresnel
+        } else {
+            let denom = dot(wi, &wm) + dot(wo, &wm) / eta;
+            let dwm_dwi = f64::abs(dot(wi, &wm)) / (denom * denom);
+            self.visible_d(wo, &wm) * dwm_dwi * (1.0 - fresnel)
+        }
+    }
+}
+
+// Microfacet normal reflecting wo into wi, if both are above the surface
+pub fn reflection_normal(wo: &Vec3, wi: &Vec3) -> Option<Vec3> {
+    if wo.z <= 0.0 || wi.z <= 0.0 {
+        return None;
+    }
+    let wm = *wo + *wi;
+    if wm.near_zero() {
+        return None;
+    }
+    Some(wm.unit_vector())
+}
+
+// Microfacet normal turning wo into wi at an interface of relative index eta, facing wo, if a
+// microfacet visible from both can do it
+fn dielectric_normal(wo: &Vec3, wi: &Vec3, eta: f64) -> Option<Vec3> {
+    let etap = if wi.z > 0.0 { 1.0 } else { eta };
+    let wm = *wi * etap + *wo;
+    if wo.z <= 0.0 || wi.z == 0.0 || wm.near_zero() {
+        return None;
+    }
+
+    let wm = wm.unit_vector();
+    let wm = if wm.z < 0.0 { -wm } else { wm };
+    if dot(&wm, wi) * wi.z < 0.0 || dot(&wm, wo) * wo.z < 0.0 {
+        return None;
+    }
+    Some(wm)
+}
+
+// Fresnel reflectance of unpolarized light at a dielectric interface, with eta the index of the
+// side light goes into over the index of the side it comes from. One under total internal reflection.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_theta_t = f64::sqrt(1.0 - sin2_theta_t);
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+// Lambda of directions tangent to the surface, which see no microfacet unshadowed
+const INFINITY_LAMBDA: f64 = 1e30;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Midpoint rule over the hemisphere, in φ and t with cos θ = 1 - t² to follow the peak of
+    // smooth distributions around the normal
+    fn integrate_hemisphere(steps: usize, f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (dt, dphi) = (1.0 / steps as f64, 2.0 * PI / steps as f64);
+        let mut total = 0.0;
+        for i in 0..steps {
+            let t = (i as f64 + 0.5) * dt;
+            let cos_theta = 1.0 - t * t;
+            let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+            for j in 0..steps {
+                let phi = (j as f64 + 0.5) * dphi;
+                total += f(&Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)) * 2.0 * t * dt * dphi;
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn microfacets_cover_the_surface() {
+        for roughness in [0.3, 0.6, 1.0] {
+            let distribution = TrowbridgeReitz::new(roughness);
+            let projected = integrate_hemisphere(1000, |wm| distribution.d(wm) * wm.z);
+            assert!((projected - 1.0).abs() < 1e-3, "roughness {roughness}: {projected}");
+        }
+    }
+
+    #[test]
+    fn visible_normals_are_normalized() {
+        let w = Vec3::new(0.6, 0.0, 0.8);
+        for roughness in [0.3, 0.6, 1.0] {
+            let distribution = TrowbridgeReitz::new(roughness);
+            let total = integrate_hemisphere(1000, |wm| distribution.visible_d(&w, wm));
+            assert!((total - 1.0).abs() < 1e-3, "roughness {roughness}: {total}");
+        }
+    }
+
+    #[test]
+    fn fresnel_dielectric_limits() {
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+        assert_eq!(fresnel_dielectric(0.1, 1.0 / 1.5), 1.0);
+        assert!((fresnel_dielectric(0.0, 1.5) - 1.0).abs() < 1e-12);
+    }
+}
@@ -4,8 +4,41 @@ pub use self::scattering_function::ScatteringFunction;
 pub mod lambertian;
 pub use self::lambertian::Lambertian;
 
+pub mod oren_nayar;
+pub use self::oren_nayar::OrenNayar;
+
 pub mod specular;
 pub use self::specular::Specular;
 
+pub mod metal;
+pub use self::metal::Metal;
+
+pub mod absorption;
+pub use self::absorption::Absorption;
+
+pub mod thin_film;
+pub use self::thin_film::ThinFilm;
+
+pub mod microfacet;
+pub use self::microfacet::TrowbridgeReitz;
+
+pub mod conductor;
+pub use self::conductor::Conductor;
+
+pub mod rough_dielectric;
+pub use self::rough_dielectric::RoughDielectric;
+
+pub mod principled;
+pub use self::principled::{Principled, PrincipledParams};
+
+pub mod subsurface;
+pub use self::subsurface::Subsurface;
+
 pub mod refractive;
-pub use self::refractive::Refractive;
\ No newline at end of file
+pub use self::refractive::Refractive;
+
+pub mod isotropic;
+pub use self::isotropic::Isotropic;
+
+pub mod henyey_greenstein;
+pub use self::henyey_greenstein::HenyeyGreenstein;
@@ -0,0 +1,124 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::textures::{Texture, SolidColor};
+use super::microfacet::Frame;
+use super::scattering_function::ScatteringFunction;
+
+// Rough diffuse surface after Oren and Nayar, made of tiny V-shaped Lambertian facets whose
+// slopes have a standard deviation of sigma (in degrees). Rough surfaces such as clay or concrete
+// look flatter than Lambertian ones and reflect more back towards the light. With no roughness
+// it is Lambertian.
+pub struct OrenNayar {
+    texture: Arc<dyn Texture>,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    pub fn new(albedo: Color, sigma: f64) -> Arc<dyn ScatteringFunction> {
+        let solid_color_texture = Arc::new(SolidColor::new(albedo)) as Arc<dyn Texture>;
+        Self::new_from_texture(solid_color_texture, sigma)
+    }
+
+    pub fn new_from_texture(texture: Arc<dyn Texture>, sigma: f64) -> Arc<dyn ScatteringFunction> {
+        let sigma = f64::max(sigma, 0.0).to_radians();
+        let sigma2 = sigma * sigma;
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+        Arc::new(OrenNayar { texture, a, b }) as Arc<dyn ScatteringFunction>
+    }
+
+    // BSDF over the albedo / π, for directions in the local frame of the surface
+    fn factor(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let sin_theta_o = f64::sqrt(f64::max(0.0, 1.0 - wo.z * wo.z));
+        let sin_theta_i = f64::sqrt(f64::max(0.0, 1.0 - wi.z * wi.z));
+        if sin_theta_o < 1e-4 || sin_theta_i < 1e-4 {
+            return self.a;
+        }
+
+        // cos(φi - φo), only facing azimuths brighten the surface
+        let cos_phi = f64::max(0.0, (wi.x * wo.x + wi.y * wo.y) / (sin_theta_i * sin_theta_o));
+
+        // sin α tan β, with α the larger of the polar angles and β the smaller one
+        let (sin_alpha, tan_beta) = if f64::abs(wi.z) > f64::abs(wo.z) {
+            (sin_theta_o, sin_theta_i / f64::abs(wi.z))
+        } else {
+            (sin_theta_i, sin_theta_o / f64::abs(wo.z))
+        };
+        self.a + self.b * cos_phi * sin_alpha * tan_beta
+    }
+}
+
+impl ScatteringFunction for OrenNayar {
+    // Cosine distributed directions as Lambertian, weighted by the roughness factor
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
+        if scatter_direction.near_zero() {
+            scatter_direction = rec.normal;
+        }
+
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        let wi = frame.to_local(&scatter_direction.unit_vector());
+
+        *scattered = Ray::new(rec.p, scatter_direction);
+        *attenuation = self.texture.value(rec.u, rec.v, &rec.p) * self.factor(&wo, &wi);
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        let wi = frame.to_local(&scattered.dir.unit_vector());
+        if wi.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        self.texture.value(rec.u, rec.v, &rec.p) * (self.factor(&wo, &wi) * wi.z / PI)
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = dot(&rec.normal, &scattered.dir.unit_vector());
+        f64::max(cos_theta, 0.0) / PI
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use crate::materials::scattering_function::Lambertian;
+
+    fn hit() -> HitRecord {
+        let material = Arc::new(Material::new_from_lobe(Lambertian::new(Color::new(0.5, 0.5, 0.5)), None));
+        HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), material, 1.0, 0.0, 0.0, true)
+    }
+
+    fn assert_close(a: &Color, b: &Color) {
+        assert!((a.x - b.x).abs() < 1e-12 && (a.y - b.y).abs() < 1e-12 && (a.z - b.z).abs() < 1e-12,
+            "({}, {}, {}) != ({}, {}, {})", a.x, a.y, a.z, b.x, b.y, b.z);
+    }
+
+    #[test]
+    fn without_roughness_it_is_lambertian() {
+        let rec = hit();
+        let albedo = Color::new(0.8, 0.5, 0.2);
+        let smooth = OrenNayar::new(albedo, 0.0);
+        let lambertian = Lambertian::new(albedo);
+
+        for _ in 0..1000 {
+            let r_in = Ray::new(Point3::new(0.0, 0.0, 0.0), -Vec3::random_on_hemisphere(&rec.normal));
+            let scattered = Ray::new(rec.p, Vec3::random_unit_vector());
+
+            assert_close(&smooth.eval(&r_in, &rec, &scattered), &lambertian.eval(&r_in, &rec, &scattered));
+            assert!((smooth.pdf(&r_in, &rec, &scattered) - lambertian.pdf(&r_in, &rec, &scattered)).abs() < 1e-12);
+
+            let mut attenuation = Color::new(0.0, 0.0, 0.0);
+            let mut sampled = scattered;
+            assert!(smooth.scatter(&r_in, &rec, &mut attenuation, &mut sampled));
+            assert_close(&attenuation, &albedo);
+        }
+    }
+}
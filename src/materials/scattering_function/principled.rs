@@ -0,0 +1,281 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::textures::{Texture, SolidColor};
+use crate::utils::random_double;
+use super::microfacet::{Frame, TrowbridgeReitz, reflection_normal};
+use super::scattering_function::ScatteringFunction;
+
+// Lowest roughness, below it the specular lobes are too sharp to be sampled
+const MIN_ROUGHNESS: f64 = 0.01;
+
+// Roughness of the clear coat, a glossy varnish
+const CLEARCOAT_ROUGHNESS: f64 = 0.1;
+
+// Reflectance at normal incidence of the clear coat, a dielectric of index 1.5
+const CLEARCOAT_F0: f64 = 0.04;
+
+// Parameters of a principled BSDF, each read from a texture. Colors are the texture value, scalars
+// its luminance, all but the index of refraction from zero to one.
+#[derive(Clone)]
+pub struct PrincipledParams {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: Arc<dyn Texture>,       // From dielectric to metal tinted by the base color
+    pub roughness: Arc<dyn Texture>,
+    pub specular: Arc<dyn Texture>,       // Reflectance of dielectrics, 0.5 is 4% at normal incidence
+    pub specular_tint: Arc<dyn Texture>,  // Tints the dielectric reflection towards the base color
+    pub sheen: Arc<dyn Texture>,          // Grazing reflection of cloth
+    pub clearcoat: Arc<dyn Texture>,      // Glossy varnish layer on top
+    pub transmission: Arc<dyn Texture>,   // From opaque to rough glass tinted by the base color
+    pub ior: Arc<dyn Texture>,            // Index of refraction of the transmission
+}
+
+impl PrincipledParams {
+    // Texture of a constant scalar parameter
+    pub fn constant(value: f64) -> Arc<dyn Texture> {
+        Arc::new(SolidColor::new(Color::new(value, value, value))) as Arc<dyn Texture>
+    }
+}
+
+impl Default for PrincipledParams {
+    // Grey plastic
+    fn default() -> Self {
+        Self {
+            base_color: Self::constant(0.8),
+            metallic: Self::constant(0.0),
+            roughness: Self::constant(0.5),
+            specular: Self::constant(0.5),
+            specular_tint: Self::constant(0.0),
+            sheen: Self::constant(0.0),
+            clearcoat: Self::constant(0.0),
+            transmission: Self::constant(0.0),
+            ior: Self::constant(1.5),
+        }
+    }
+}
+
+// Principled BSDF after Burley's Disney model. A Burley diffuse lobe with sheen, a GGX specular
+// lobe going from the dielectric reflectance to the base color as the surface turns metallic,
+// rough glass for transmission, and a clear coat over them taking its Fresnel reflectance of the
+// light. As in Disney's model the diffuse and specular lobes are added, not layered. As for
+// Refractive, radiance is not rescaled when crossing the interface.
+pub struct Principled {
+    params: PrincipledParams,
+}
+
+impl Principled {
+    pub fn new(params: PrincipledParams) -> Arc<dyn ScatteringFunction> {
+        Arc::new(Principled { params }) as Arc<dyn ScatteringFunction>
+    }
+
+    fn lobes(&self, r_in: &Ray, rec: &HitRecord) -> Lobes {
+        let color = |texture: &Arc<dyn Texture>| texture.value(rec.u, rec.v, &rec.p);
+        let scalar = |texture: &Arc<dyn Texture>| luminance(&color(texture)).clamp(0.0, 1.0);
+
+        let base_color = color(&self.params.base_color);
+        let metallic = scalar(&self.params.metallic);
+        let roughness = f64::max(scalar(&self.params.roughness), MIN_ROUGHNESS);
+        let transmission = scalar(&self.params.transmission);
+
+        // Tint of the base color with its luminance normalized away
+        let base_luminance = luminance(&base_color);
+        let tint = if base_luminance > 0.0 { base_color / base_luminance } else { Color::new(1.0, 1.0, 1.0) };
+        let white = Color::new(1.0, 1.0, 1.0);
+        let specular_tint = scalar(&self.params.specular_tint);
+        let dielectric_f0 = 0.08 * scalar(&self.params.specular) * ((1.0 - specular_tint) * white + specular_tint * tint);
+
+        let ior = f64::max(luminance(&color(&self.params.ior)), 1.001);
+        let frame = Frame::new(&rec.normal);
+
+        Lobes {
+            wo: frame.to_local(&-r_in.dir.unit_vector()),
+            frame,
+            base_color,
+            roughness,
+            sheen: scalar(&self.params.sheen),
+            clearcoat: scalar(&self.params.clearcoat),
+            specular_f0: (1.0 - metallic) * dielectric_f0 + metallic * base_color,
+            diffuse_weight: (1.0 - metallic) * (1.0 - transmission),
+            specular_weight: 1.0 - (1.0 - metallic) * transmission,
+            glass_weight: (1.0 - metallic) * transmission,
+            eta: if rec.front_face { ior } else { 1.0 / ior },
+            distribution: TrowbridgeReitz::new(roughness),
+            coat: TrowbridgeReitz::new(CLEARCOAT_ROUGHNESS),
+        }
+    }
+}
+
+// Parameters of a principled BSDF at a hit point, in the local frame of the surface
+struct Lobes {
+    frame: Frame,
+    wo: Vec3,
+    base_color: Color,
+    roughness: f64,
+    sheen: f64,
+    clearcoat: f64,
+    specular_f0: Color,
+    diffuse_weight: f64,
+    specular_weight: f64,
+    glass_weight: f64,
+    eta: f64,   // Index of the side light goes into over the one it comes from
+    distribution: TrowbridgeReitz,
+    coat: TrowbridgeReitz,
+}
+
+impl Lobes {
+    // Share of the light going through the clear coat
+    fn base_scale(&self) -> f64 {
+        1.0 - self.clearcoat * schlick_scalar(self.wo.z, CLEARCOAT_F0)
+    }
+
+    fn transmission_tint(&self) -> Color {
+        let root = |c: f64| f64::max(c, 0.0).sqrt();
+        Color::new(root(self.base_color.x), root(self.base_color.y), root(self.base_color.z))
+    }
+
+    // Probabilities of sampling the diffuse, specular, glass and clear coat lobes, roughly in
+    // proportion to the light they scatter
+    fn probabilities(&self) -> [f64; 4] {
+        let base_scale = self.base_scale();
+        let specular = luminance(&schlick(&self.specular_f0, self.wo.z));
+        let weights = [
+            base_scale * self.diffuse_weight,
+            base_scale * self.specular_weight * specular,
+            base_scale * self.glass_weight,
+            self.clearcoat * schlick_scalar(self.wo.z, CLEARCOAT_F0),
+        ];
+
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return [0.0; 4];
+        }
+        weights.map(|weight| weight / total)
+    }
+
+    // BSDF times the cosine at wi
+    fn eval(&self, wi: &Vec3) -> Color {
+        let wo = &self.wo;
+        let white = Color::new(1.0, 1.0, 1.0);
+        let base_scale = self.base_scale();
+        let mut f = Color::new(0.0, 0.0, 0.0);
+
+        if let Some(wm) = reflection_normal(wo, wi) {
+            // Burley diffuse, brighter at grazing angles on rough surfaces, with a white sheen
+            let cos_theta_d = dot(wi, &wm);
+            let fd90 = 0.5 + 2.0 * self.roughness * cos_theta_d * cos_theta_d;
+            let retro = (1.0 + (fd90 - 1.0) * f64::powi(1.0 - wi.z, 5)) * (1.0 + (fd90 - 1.0) * f64::powi(1.0 - wo.z, 5));
+            let sheen = self.sheen * f64::powi(1.0 - cos_theta_d, 5);
+            f = f + base_scale * self.diffuse_weight * wi.z * (self.base_color * (retro / PI) + sheen * white);
+
+            let specular = schlick(&self.specular_f0, dot(wo, &wm)) * self.distribution.eval_reflection(wo, wi, &wm);
+            f = f + base_scale * self.specular_weight * specular;
+
+            let coat = schlick_scalar(dot(wo, &wm), CLEARCOAT_F0) * self.coat.eval_reflection(wo, wi, &wm);
+            f = f + self.clearcoat * coat * white;
+        }
+
+        if self.glass_weight > 0.0 {
+            // Light crossing a closed object goes through the surface twice, tint each crossing by
+            // the square root so the base color is what comes out
+            let tint = if wi.z < 0.0 { self.transmission_tint() } else { white };
+            f = f + base_scale * self.glass_weight * self.distribution.eval_dielectric(wo, wi, self.eta) * tint;
+        }
+        f
+    }
+
+    // Density of sample choosing wi through any of the lobes
+    fn pdf(&self, wi: &Vec3) -> f64 {
+        let [diffuse, specular, glass, coat] = self.probabilities();
+        let mut pdf = 0.0;
+
+        if let Some(wm) = reflection_normal(&self.wo, wi) {
+            pdf += diffuse * wi.z / PI;
+            pdf += specular * self.distribution.pdf_reflection(&self.wo, &wm);
+            pdf += coat * self.coat.pdf_reflection(&self.wo, &wm);
+        }
+        if glass > 0.0 {
+            pdf += glass * self.distribution.pdf_dielectric(&self.wo, wi, self.eta);
+        }
+        pdf
+    }
+
+    // Direction chosen by one of the lobes, picked by probabilities
+    fn sample(&self) -> Option<Vec3> {
+        let [diffuse, specular, glass, _] = self.probabilities();
+        let choice = random_double();
+
+        if choice < diffuse {
+            let direction = Vec3::new(0.0, 0.0, 1.0) + Vec3::random_unit_vector();
+            if direction.near_zero() || direction.z <= 0.0 {
+                return None;
+            }
+            Some(direction.unit_vector())
+        } else if choice < diffuse + specular {
+            self.distribution.sample_reflection(&self.wo)
+        } else if choice < diffuse + specular + glass {
+            self.distribution.sample_dielectric(&self.wo, self.eta)
+        } else {
+            self.coat.sample_reflection(&self.wo)
+        }
+    }
+}
+
+impl ScatteringFunction for Principled {
+    // The attenuation weighs the BSDF by the density of all the lobes, any of them could have
+    // chosen the direction
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        let lobes = self.lobes(r_in, rec);
+        if lobes.wo.z <= 0.0 {
+            return false;
+        }
+
+        let wi = match lobes.sample() {
+            Some(wi) => wi,
+            None => return false,
+        };
+        let pdf = lobes.pdf(&wi);
+        if pdf <= 0.0 {
+            return false;
+        }
+
+        *scattered = Ray::new(rec.p, lobes.frame.from_local(&wi));
+        *attenuation = lobes.eval(&wi) / pdf;
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let lobes = self.lobes(r_in, rec);
+        if lobes.wo.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        lobes.eval(&lobes.frame.to_local(&scattered.dir.unit_vector()))
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let lobes = self.lobes(r_in, rec);
+        if lobes.wo.z <= 0.0 {
+            return 0.0;
+        }
+        lobes.pdf(&lobes.frame.to_local(&scattered.dir.unit_vector()))
+    }
+
+    fn adjoint_scale(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        // Only transmitted rays change of medium
+        if dot(&scattered.dir, &rec.normal) >= 0.0 {
+            return 1.0;
+        }
+        let eta = self.lobes(r_in, rec).eta;
+        1.0 / (eta * eta)
+    }
+}
+
+// Schlick's approximation of the Fresnel reflectance for a reflectance f0 at normal incidence
+fn schlick(f0: &Color, cos_theta: f64) -> Color {
+    let grazing = f64::powi(1.0 - cos_theta.clamp(0.0, 1.0), 5);
+    *f0 + (Color::new(1.0, 1.0, 1.0) - *f0) * grazing
+}
+
+fn schlick_scalar(cos_theta: f64, f0: f64) -> f64 {
+    f0 + (1.0 - f0) * f64::powi(1.0 - cos_theta.clamp(0.0, 1.0), 5)
+}
@@ -1,43 +1,152 @@
 use std::sync::Arc;
 use crate::primitives::*;
 use crate::hittable::HitRecord;
+use crate::spectrum::{ComplexIor, RefractiveIndex};
 use crate::utils::random_double;
+use super::absorption::Absorption;
+use super::thin_film::ThinFilm;
 use super::scattering_function::ScatteringFunction;
 
 #[derive(Default)]
 pub struct Refractive {
-    refraction_index: f64,
+    refraction_index: RefractiveIndex,
+    absorption: Absorption,     // Of the medium inside, clear by default
+    film: Option<ThinFilm>,     // Coating on the outside, for soap bubbles and coated lenses
 }
 
 impl Refractive {
     pub fn new(refraction_index: f64) -> Arc<dyn ScatteringFunction> {
-        Arc::new(Refractive { refraction_index }) as Arc<dyn ScatteringFunction>
+        Self::new_dispersive(RefractiveIndex::Constant(refraction_index))
+    }
+
+    // Index following Cauchy's equation n = a + b / λ², with λ in micrometers
+    pub fn new_cauchy(a: f64, b: f64) -> Arc<dyn ScatteringFunction> {
+        Self::new_dispersive(RefractiveIndex::Cauchy { a, b })
+    }
+
+    // Index following Sellmeier's equation with coefficients for λ in micrometers
+    pub fn new_sellmeier(b: [f64; 3], c: [f64; 3]) -> Arc<dyn ScatteringFunction> {
+        Self::new_dispersive(RefractiveIndex::Sellmeier { b, c })
+    }
+
+    pub fn new_dispersive(refraction_index: RefractiveIndex) -> Arc<dyn ScatteringFunction> {
+        Self::new_absorbing(refraction_index, Absorption::default())
+    }
+
+    // Colored glass or liquid, darkening the light along its path inside
+    pub fn new_absorbing(refraction_index: RefractiveIndex, absorption: Absorption) -> Arc<dyn ScatteringFunction> {
+        Arc::new(Refractive { refraction_index, absorption, film: None }) as Arc<dyn ScatteringFunction>
+    }
+
+    // Interface coated by a thin film, a soap bubble being a film over an index of one
+    pub fn new_with_film(refraction_index: RefractiveIndex, absorption: Absorption, film: ThinFilm) -> Arc<dyn ScatteringFunction> {
+        Arc::new(Refractive { refraction_index, absorption, film: Some(film) }) as Arc<dyn ScatteringFunction>
+    }
+
+    // Share of the light reflected, arriving at an angle of cosine cos_theta that can refract
+    fn reflectance(&self, rec: &HitRecord, cos_theta: f64, refraction_index: f64) -> Color {
+        let film = match &self.film {
+            Some(film) => film,
+            None => {
+                let ratio = if rec.front_face { 1.0 / refraction_index } else { refraction_index };
+                let r = reflectance(cos_theta, ratio);
+                return Color::new(r, r, r);
+            }
+        };
+
+        let black = Color::new(0.0, 0.0, 0.0);
+        if rec.front_face {
+            let inside = Color::new(refraction_index, refraction_index, refraction_index);
+            film.reflectance(rec, cos_theta, 1.0, &ComplexIor::new(inside, black))
+        } else {
+            film.reflectance(rec, cos_theta, refraction_index, &ComplexIor::new(Color::new(1.0, 1.0, 1.0), black))
+        }
     }
 }
 
 impl ScatteringFunction for Refractive {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
-        *attenuation = Color::new(1.0, 1.0, 1.0);  // No color attenuation for refraction
+        // Only the medium the ray crossed to get here colors the light
+        *attenuation = self.absorption.transmittance(r_in, rec);
+        let refraction_index = self.refraction_index.value(&r_in.wavelengths);
         let refraction_ratio = if rec.front_face {
-            1.0 / self.refraction_index
+            1.0 / refraction_index
         } else {
-            self.refraction_index
+            refraction_index
         };
         
         let unit_direction: Vec3 = r_in.dir.unit_vector();
         let cos_theta = f64::min(dot(&-unit_direction, &rec.normal), 1.0);
         let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
 
-        let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction = if cannot_refract || reflectance(cos_theta, refraction_ratio) > random_double() {
-            reflect(&unit_direction, &rec.normal)
+        if refraction_ratio * sin_theta > 1.0 {
+            *scattered = Ray::new(rec.p, reflect(&unit_direction, &rec.normal));
+            return true;
+        }
+
+        // Reflect with the average reflectance, the attenuation keeps what the channels differ by
+        let reflectance = self.reflectance(rec, cos_theta, refraction_index);
+        let probability = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
+        if probability > random_double() {
+            *scattered = Ray::new(rec.p, reflect(&unit_direction, &rec.normal));
+            *attenuation = *attenuation * reflectance / probability;
+        } else {
+            *scattered = Ray::new(rec.p, refract(&unit_direction, &rec.normal, refraction_ratio));
+            *attenuation = *attenuation * (Color::new(1.0, 1.0, 1.0) - reflectance) / (1.0 - probability);
+        }
+        true
+    }
+
+    fn adjoint_scale(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        // Only transmitted rays change of medium
+        if dot(&scattered.dir, &rec.normal) >= 0.0 {
+            return 1.0;
+        }
+
+        let refraction_index = self.refraction_index.value(&r_in.wavelengths);
+        let refraction_ratio = if rec.front_face {
+            1.0 / refraction_index
         } else {
-            refract(&unit_direction, &rec.normal, refraction_ratio)
+            refraction_index
         };
+        refraction_ratio * refraction_ratio
+    }
 
-        *scattered = Ray::new(rec.p, direction);
+    fn is_dispersive(&self) -> bool {
+        self.refraction_index.is_dispersive()
+    }
+
+    fn is_specular(&self) -> bool {
         true
     }
+
+    fn specular_directions(&self, r_in: &Ray, rec: &HitRecord) -> Vec<(Color, Ray)> {
+        let refraction_index = self.refraction_index.value(&r_in.wavelengths);
+        let refraction_ratio = if rec.front_face {
+            1.0 / refraction_index
+        } else {
+            refraction_index
+        };
+
+        // Reflected and refracted rays weighted by the Fresnel reflectance, or the reflected one
+        // alone under total internal reflection
+        let unit_direction: Vec3 = r_in.dir.unit_vector();
+        let cos_theta = f64::min(dot(&-unit_direction, &rec.normal), 1.0);
+        let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+        let transmittance = self.absorption.transmittance(r_in, rec);
+
+        let reflected = Ray::new(rec.p, reflect(&unit_direction, &rec.normal));
+        if refraction_ratio * sin_theta > 1.0 {
+            return vec![(transmittance, reflected)];
+        }
+
+        let reflectance = self.reflectance(rec, cos_theta, refraction_index);
+        let refracted = Ray::new(rec.p, refract(&unit_direction, &rec.normal, refraction_ratio));
+        vec![
+            (reflectance * transmittance, reflected),
+            ((Color::new(1.0, 1.0, 1.0) - reflectance) * transmittance, refracted),
+        ]
+    }
 }
 
 // Schlick Approximation for Fresnel reflectance
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::spectrum::RefractiveIndex;
+use crate::textures::{Texture, SolidColor};
+use super::absorption::Absorption;
+use super::microfacet::{Frame, TrowbridgeReitz};
+use super::scattering_function::ScatteringFunction;
+
+// Lowest roughness, below it the distribution is too sharp to be sampled. Use Refractive for
+// perfectly smooth glass.
+const MIN_ROUGHNESS: f64 = 0.01;
+
+// Frosted glass after Walter et al.: GGX microfacets that each reflect or refract as a smooth
+// interface would, chosen by their Fresnel reflectance. The roughness can vary over the surface
+// with a texture, its luminance read from zero to one. As for Refractive, radiance is not rescaled
+// when crossing the interface, and the medium inside can absorb.
+pub struct RoughDielectric {
+    refraction_index: RefractiveIndex,
+    roughness: Arc<dyn Texture>,
+    absorption: Absorption,
+}
+
+impl RoughDielectric {
+    pub fn new(refraction_index: f64, roughness: f64) -> Arc<dyn ScatteringFunction> {
+        let roughness_texture = Arc::new(SolidColor::new(Color::new(roughness, roughness, roughness))) as Arc<dyn Texture>;
+        Self::new_from_texture(RefractiveIndex::Constant(refraction_index), roughness_texture)
+    }
+
+    pub fn new_from_texture(refraction_index: RefractiveIndex, roughness: Arc<dyn Texture>) -> Arc<dyn ScatteringFunction> {
+        Self::new_absorbing(refraction_index, roughness, Absorption::default())
+    }
+
+    // Frosted colored glass, darkening the light along its path inside
+    pub fn new_absorbing(refraction_index: RefractiveIndex, roughness: Arc<dyn Texture>, absorption: Absorption) -> Arc<dyn ScatteringFunction> {
+        Arc::new(RoughDielectric { refraction_index, roughness, absorption }) as Arc<dyn ScatteringFunction>
+    }
+
+    fn distribution(&self, rec: &HitRecord) -> TrowbridgeReitz {
+        let roughness = luminance(&self.roughness.value(rec.u, rec.v, &rec.p));
+        TrowbridgeReitz::new(f64::max(roughness, MIN_ROUGHNESS))
+    }
+
+    // Index of the side the ray goes into over the one it comes from
+    fn eta(&self, r_in: &Ray, rec: &HitRecord) -> f64 {
+        let refraction_index = self.refraction_index.value(&r_in.wavelengths);
+        if rec.front_face {
+            refraction_index
+        } else {
+            1.0 / refraction_index
+        }
+    }
+}
+
+impl ScatteringFunction for RoughDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        // Reflect or refract on the microfacet as chosen by its Fresnel reflectance, so the
+        // reflectance cancels out of the attenuation
+        let distribution = self.distribution(rec);
+        let wi = match distribution.sample_dielectric(&wo, self.eta(r_in, rec)) {
+            Some(wi) => wi,
+            None => return false,
+        };
+
+        *scattered = Ray::new(rec.p, frame.from_local(&wi));
+        let shadowing = distribution.g(&wo, &wi) / distribution.g1(&wo);
+        *attenuation = shadowing * self.absorption.transmittance(r_in, rec);
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        let wi = frame.to_local(&scattered.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return black;
+        }
+
+        // Times the cosine at wi, which cancels out
+        let value = self.distribution(rec).eval_dielectric(&wo, &wi, self.eta(r_in, rec));
+        value * self.absorption.transmittance(r_in, rec)
+    }
+
+    // Density of the visible normal, times the Jacobian of turning it into wi and the
+    // probability of reflecting or refracting
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let frame = Frame::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir.unit_vector());
+        let wi = frame.to_local(&scattered.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+
+        self.distribution(rec).pdf_dielectric(&wo, &wi, self.eta(r_in, rec))
+    }
+
+    fn adjoint_scale(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        // Only transmitted rays change of medium
+        if dot(&scattered.dir, &rec.normal) >= 0.0 {
+            return 1.0;
+        }
+        let eta = self.eta(r_in, rec);
+        1.0 / (eta * eta)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.refraction_index.is_dispersive()
+    }
+}
@@ -3,4 +3,46 @@ use crate::hittable::HitRecord;
 
 pub trait ScatteringFunction: Send + Sync {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool;
+
+    // BSDF times the cosine term for a given pair of incoming and scattered directions,
+    // used when light is sampled explicitly. Lobes that can only be sampled keep the default.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    // Solid angle pdf with which scatter would have chosen the scattered direction
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    // Factor to apply to the attenuation when the ray carries light from the lights instead of
+    // gathering it from the camera. Only refraction is not symmetric: radiance is not rescaled
+    // when crossing an interface, so light flowing the other way has to be.
+    fn adjoint_scale(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0
+    }
+
+    // Lobes whose scattered direction depends on the wavelength. Spectral paths keep only their
+    // hero wavelength after them.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    // Perfect mirrors and glass scatter in a single direction, so sampling lights from them is useless
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    // Whether scatter chose the scattered ray through a specular event, which eval and pdf leave
+    // out. Lobes mixing specular and non specular events tell them apart here.
+    fn is_delta(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> bool {
+        self.is_specular()
+    }
+
+    // Every direction a specular lobe can scatter into, with the attenuation of the light taking
+    // it, for integrators following all of them instead of choosing one at random. Empty for the
+    // lobes that are not specular.
+    fn specular_directions(&self, _r_in: &Ray, _rec: &HitRecord) -> Vec<(Color, Ray)> {
+        Vec::new()
+    }
 }
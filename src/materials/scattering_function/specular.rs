@@ -20,4 +20,13 @@ impl ScatteringFunction for Specular {
         *scattered = Ray::new(rec.p, direction);
         true
     }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    fn specular_directions(&self, r_in: &Ray, rec: &HitRecord) -> Vec<(Color, Ray)> {
+        let direction = reflect(&r_in.dir.unit_vector(), &rec.normal);
+        vec![(Color::new(1.0, 1.0, 1.0), Ray::new(rec.p, direction))]
+    }
 }
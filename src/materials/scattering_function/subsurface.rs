@@ -0,0 +1,200 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::utils::random_double;
+use super::microfacet::{Frame, fresnel_dielectric};
+use super::scattering_function::ScatteringFunction;
+
+// Distance, relative to the hit point, under which a ray is taken to start at it
+const START_TOLERANCE: f64 = 1e-9;
+
+// Translucent material such as skin, wax, marble or milk, for closed objects. Light crosses a
+// dielectric boundary and random walks inside, scattered isotropically by a medium of the given
+// albedo and mean free path (average distance between collisions, per channel), until it leaves
+// the object again.
+//
+// The walk is followed one segment at a time. Rays reaching a back face come from inside, and
+// the medium decides whether they collided on their way to it, going on from that point, or got
+// to the boundary. There, as on the outside, light is reflected or crosses it as the Fresnel
+// reflectance says. Crossing light is diffused, as by the boundary of pbrt's subsurface
+// materials, both ways so that light entering and leaving follow the same law. Leaving is the
+// only event with a density, so lights are sampled at the exit points. Entering, collisions and
+// reflections go on inside, where nothing can be connected to, and are sampled as specular events.
+pub struct Subsurface {
+    albedo: Color,
+    extinction: Color,  // Collisions per unit distance, one over the mean free path
+    refraction_index: f64,
+    diffuse_transmittance: f64,  // Share of diffuse light from outside crossing into the object
+}
+
+impl Subsurface {
+    pub fn new(albedo: Color, mean_free_path: Color, refraction_index: f64) -> Arc<dyn ScatteringFunction> {
+        let extinction = |mfp: f64| 1.0 / f64::max(mfp, 1e-6);
+
+        // Twice the integral of the transmitted share times the cosine over the cosines
+        let steps = 1000;
+        let diffuse_transmittance = (0..steps)
+            .map(|i| (i as f64 + 0.5) / steps as f64)
+            .map(|cos_theta| 2.0 * (1.0 - fresnel_dielectric(cos_theta, refraction_index)) * cos_theta / steps as f64)
+            .sum();
+
+        Arc::new(Subsurface {
+            albedo,
+            extinction: Color::new(extinction(mean_free_path.x), extinction(mean_free_path.y), extinction(mean_free_path.z)),
+            refraction_index,
+            diffuse_transmittance,
+        }) as Arc<dyn ScatteringFunction>
+    }
+
+    // Index of the side the ray goes into over the one it comes from
+    fn eta(&self, rec: &HitRecord) -> f64 {
+        if rec.front_face {
+            self.refraction_index
+        } else {
+            1.0 / self.refraction_index
+        }
+    }
+
+    // Fresnel reflectance of the boundary for light arriving along r_in
+    fn reflectance(&self, r_in: &Ray, rec: &HitRecord) -> f64 {
+        let cos_theta = f64::min(dot(&-r_in.dir.unit_vector(), &rec.normal), 1.0);
+        fresnel_dielectric(cos_theta, self.eta(rec))
+    }
+
+    // Share of every channel crossing the given distance inside without colliding
+    fn transmittance(&self, distance: f64) -> Color {
+        let sigma = self.extinction;
+        Color::new(f64::exp(-sigma.x * distance), f64::exp(-sigma.y * distance), f64::exp(-sigma.z * distance))
+    }
+
+    // For the side entered by light crossing the boundary at rec, the largest squared sine light
+    // can cross back from (beyond it, it is totally reflected) and the diffuse transmittance back
+    fn side_entered(&self, rec: &HitRecord) -> (f64, f64) {
+        let eta = self.eta(rec);
+        let cone = f64::min(1.0, 1.0 / (eta * eta));
+        if rec.front_face {
+            // Diffuse light gets out of a denser medium 1 / η² as much as it gets in
+            (cone, self.diffuse_transmittance / (eta * eta))
+        } else {
+            (cone, self.diffuse_transmittance)
+        }
+    }
+
+    // Reflect or cross the boundary as chosen by the Fresnel reflectance, with the weight of the
+    // ray. Crossing light is diffused in proportion to the share of it that could cross back,
+    // sampling only the directions it can cross back from.
+    fn cross_boundary(&self, r_in: &Ray, rec: &HitRecord) -> (Ray, f64) {
+        if random_double() < self.reflectance(r_in, rec) {
+            return (Ray::new(rec.p, reflect(&r_in.dir.unit_vector(), &rec.normal)), 1.0);
+        }
+
+        let (cone, transmittance) = self.side_entered(rec);
+        let cos_theta = f64::sqrt(1.0 - cone * random_double());
+        let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+        let phi = 2.0 * PI * random_double();
+        let local = Vec3::new(sin_theta * f64::cos(phi), sin_theta * f64::sin(phi), cos_theta);
+
+        let direction = Frame::new(&-rec.normal).from_local(&local);
+        let weight = (1.0 - fresnel_dielectric(cos_theta, 1.0 / self.eta(rec))) * cone / transmittance;
+        (Ray::new(rec.p, direction), weight)
+    }
+
+    // Cosine of the scattered ray with the outward normal, for rays leaving through the back face
+    // hit from inside. Zero for the other rays.
+    fn cos_leaving(rec: &HitRecord, scattered: &Ray) -> f64 {
+        if rec.front_face || !starts_at(scattered, &rec.p) {
+            return 0.0;
+        }
+        f64::max(0.0, dot(&-rec.normal, &scattered.dir.unit_vector()))
+    }
+}
+
+impl ScatteringFunction for Subsurface {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        if rec.front_face {
+            let (ray, weight) = self.cross_boundary(r_in, rec);
+            *attenuation = Color::new(weight, weight, weight);
+            *scattered = ray;
+            return true;
+        }
+
+        // Distance to a collision sampled for a channel chosen at random, weighted by the average
+        // density of the three channels
+        let sigma = self.extinction;
+        let channel = usize::min((random_double() * 3.0) as usize, 2);
+        let distance = -f64::ln(1.0 - random_double()) / sigma[channel];
+        let length = r_in.dir.length();
+        let distance_inside = rec.t * length;
+
+        if distance < distance_inside {
+            // Collision inside, light goes on from it in any direction
+            let density = sigma * self.transmittance(distance);
+            *attenuation = self.albedo * density / average(&density);
+            *scattered = Ray::new(r_in.at(distance / length), Vec3::random_unit_vector());
+        } else {
+            let transmitted = self.transmittance(distance_inside);
+            let (ray, weight) = self.cross_boundary(r_in, rec);
+            *attenuation = transmitted * (weight / average(&transmitted));
+            *scattered = ray;
+        }
+        true
+    }
+
+    // Light reaching the boundary from inside without colliding and leaving towards scattered
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let cos_theta = Self::cos_leaving(rec, scattered);
+        if cos_theta <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let (_, transmittance) = self.side_entered(rec);
+        let leaving = (1.0 - self.reflectance(r_in, rec)) * (1.0 - fresnel_dielectric(cos_theta, 1.0 / self.eta(rec)));
+        self.transmittance(rec.t * r_in.dir.length()) * (leaving * cos_theta / (PI * transmittance))
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = Self::cos_leaving(rec, scattered);
+        let (cone, _) = self.side_entered(rec);
+        if cos_theta <= 0.0 || 1.0 - cos_theta * cos_theta > cone {
+            return 0.0;
+        }
+
+        let reached = average(&self.transmittance(rec.t * r_in.dir.length()));
+        reached * (1.0 - self.reflectance(r_in, rec)) * cos_theta / (PI * cone)
+    }
+
+    fn adjoint_scale(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        // Only rays crossing the boundary change of medium, collisions start elsewhere
+        if !starts_at(scattered, &rec.p) || dot(&scattered.dir, &rec.normal) >= 0.0 {
+            return 1.0;
+        }
+        let eta = self.eta(rec);
+        1.0 / (eta * eta)
+    }
+
+    fn is_delta(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> bool {
+        Self::cos_leaving(rec, scattered) <= 0.0
+    }
+
+    // Only the reflection off the boundary, the walk inside cannot be followed deterministically
+    fn specular_directions(&self, r_in: &Ray, rec: &HitRecord) -> Vec<(Color, Ray)> {
+        if !rec.front_face {
+            return Vec::new();
+        }
+        let r = self.reflectance(r_in, rec);
+        vec![(Color::new(r, r, r), Ray::new(rec.p, reflect(&r_in.dir.unit_vector(), &rec.normal)))]
+    }
+}
+
+// Whether the ray leaves from the hit point, as boundary events do, and not from a collision inside.
+// Points recomputed along a path may differ in the last bits, compare with a tolerance relative to
+// their distance to the origin.
+fn starts_at(ray: &Ray, p: &Point3) -> bool {
+    let scale = f64::max(1.0, p.length_squared());
+    (ray.orig - *p).length_squared() <= START_TOLERANCE * START_TOLERANCE * scale
+}
+
+fn average(c: &Color) -> f64 {
+    (c.x + c.y + c.z) / 3.0
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use crate::primitives::*;
+use crate::hittable::HitRecord;
+use crate::spectrum::{ComplexIor, reflectance_to_rgb, thin_film_reflectance};
+use crate::textures::{Texture, SolidColor};
+
+// Transparent film a few hundred nanometers thick over a surface, as soap, oil or the coating of
+// a lens. Light reflected on both sides of the film interferes, reinforcing some wavelengths and
+// cancelling others depending on the thickness and the angle, which gives iridescent colors.
+#[derive(Clone)]
+pub struct ThinFilm {
+    thickness: Arc<dyn Texture>,
+    max_thickness: f64,     // Thickness in nanometers of a texture value of luminance one
+    ior: f64,
+}
+
+impl ThinFilm {
+    // Film of a constant thickness in nanometers
+    pub fn new(thickness: f64, ior: f64) -> Self {
+        let texture = Arc::new(SolidColor::new(Color::new(1.0, 1.0, 1.0))) as Arc<dyn Texture>;
+        Self::new_from_texture(texture, thickness, ior)
+    }
+
+    // Film whose thickness is the luminance of a texture times max_thickness, in nanometers
+    pub fn new_from_texture(thickness: Arc<dyn Texture>, max_thickness: f64, ior: f64) -> Self {
+        Self { thickness, max_thickness, ior }
+    }
+
+    // RGB reflectance for light arriving at an angle with the given cosine, from a medium of
+    // index outside, onto the film over a substrate of complex index substrate
+    pub fn reflectance(&self, rec: &HitRecord, cos_theta: f64, outside: f64, substrate: &ComplexIor) -> Color {
+        let thickness = luminance(&self.thickness.value(rec.u, rec.v, &rec.p)) * self.max_thickness;
+        reflectance_to_rgb(|lambda| {
+            let (eta, k) = substrate.at(lambda);
+            thin_film_reflectance(cos_theta, lambda, thickness, outside, self.ior, eta, k)
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::hittable::{Hittable, HitRecord};
+use crate::materials::{Material, ScatteringFunction, Specular, Refractive, Isotropic, HenyeyGreenstein};
+use crate::bvh::AABBox;
+use crate::utils::{random_double, INFINITY, NEG_INFINITY};
+
+// Participating medium of constant density filling a closed boundary (a sphere, a box...).
+// Instead of a surface, rays hit the points where they collide with the particles of the medium,
+// at distances sampled from the free-flight distribution.
+//
+// Absorption and scattering may differ per channel. Collisions are sampled with the largest
+// extinction of the three channels and the channels with less extinction make up the difference
+// with null collisions, which leave the ray going straight. Those are returned as specular hits,
+// so media with the same extinction in every channel never produce them.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable + Send + Sync>,
+    majorant: f64,                  // Extinction used to sample the collisions
+    scatter_probability: f64,       // Probability of a collision being a real one
+    scattering: Arc<Material>,      // Real collisions, scattering with the phase function
+    null: Arc<Material>,            // Null collisions
+}
+
+impl ConstantMedium {
+    // Isotropic medium with the given absorption and scattering coefficients (per unit of length)
+    pub fn new(boundary: Arc<dyn Hittable + Send + Sync>, sigma_a: Color, sigma_s: Color) -> Self {
+        Self::build(boundary, sigma_a, sigma_s, |albedo| Isotropic::new(albedo))
+    }
+
+    // Medium scattering with a Henyey-Greenstein phase function of asymmetry g
+    pub fn new_henyey_greenstein(boundary: Arc<dyn Hittable + Send + Sync>, sigma_a: Color, sigma_s: Color, g: f64) -> Self {
+        Self::build(boundary, sigma_a, sigma_s, |albedo| HenyeyGreenstein::new(albedo, g))
+    }
+
+    fn build(boundary: Arc<dyn Hittable + Send + Sync>, sigma_a: Color, sigma_s: Color, phase: impl Fn(Color) -> Arc<dyn ScatteringFunction>) -> Self {
+        let sigma_t = sigma_a + sigma_s;
+        let majorant = max_component(&sigma_t);
+        let sigma_n = Color::new(majorant - sigma_t.x, majorant - sigma_t.y, majorant - sigma_t.z);
+
+        // Real and null collisions are chosen in proportion to their largest coefficient, absorption
+        // is accounted by the weights of both
+        let scatter_max = max_component(&sigma_s);
+        let null_max = max_component(&sigma_n);
+        let scatter_probability = if scatter_max + null_max > 0.0 {
+            scatter_max / (scatter_max + null_max)
+        } else {
+            0.0
+        };
+
+        let scattering = if scatter_probability > 0.0 {
+            let weight = sigma_s / (majorant * scatter_probability);
+            Material::new(phase(weight), Specular::new(), Refractive::new(1.0), None, 1.0, 0.0, 0.0, 0.0)
+        } else {
+            // Only absorption, collisions end the paths
+            Material::new(phase(Color::new(0.0, 0.0, 0.0)), Specular::new(), Refractive::new(1.0), None, 0.0, 0.0, 0.0, 1.0)
+        };
+
+        let null_weight = if scatter_probability < 1.0 {
+            sigma_n / (majorant * (1.0 - scatter_probability))
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        };
+        let null = Material::new(phase(Color::new(0.0, 0.0, 0.0)), Arc::new(NullCollision { weight: null_weight }), Refractive::new(1.0), None, 0.0, 1.0, 0.0, 0.0);
+
+        Self {
+            boundary,
+            majorant,
+            scatter_probability,
+            scattering: Arc::new(scattering),
+            null: Arc::new(null),
+        }
+    }
+}
+
+impl ConstantMedium {
+    // Section of the ray inside the boundary, clipped to ray_t
+    fn inside(&self, r: &Ray, ray_t: &Interval) -> Option<(f64, f64)> {
+        let rec1 = self.boundary.hit(r, &mut Interval::new(NEG_INFINITY, INFINITY))?;
+        let rec2 = self.boundary.hit(r, &mut Interval::new(rec1.t + 0.0001, INFINITY))?;
+
+        let t_enter = f64::max(f64::max(rec1.t, ray_t.min), 0.0);
+        let t_exit = f64::min(rec2.t, ray_t.max);
+        if t_enter >= t_exit {
+            return None;
+        }
+        Some((t_enter, t_exit))
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        if self.majorant <= 0.0 {
+            return None;
+        }
+
+        let (t_enter, t_exit) = self.inside(r, ray_t)?;
+
+        let ray_length = r.dir.length();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = -f64::ln(1.0 - random_double()) / self.majorant;
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let mat = if random_double() < self.scatter_probability {
+            self.scattering.clone()
+        } else {
+            self.null.clone()
+        };
+
+        let t = t_enter + hit_distance / ray_length;
+        Some(HitRecord::new(r.at(t), Vec3::new(0.0, 0.0, 0.0), mat, t, 0.0, 0.0, true))
+    }
+
+    fn bounding_box(&self) -> AABBox {
+        self.boundary.bounding_box()
+    }
+
+    // Share of the light crossing the medium without any collision, null ones included. The
+    // light going through null collisions is carried by the paths continuing at them, so this
+    // only matches Beer-Lambert law when every channel has the same extinction.
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> Color {
+        match self.inside(r, ray_t) {
+            Some((t_enter, t_exit)) => {
+                let transmittance = f64::exp(-self.majorant * (t_exit - t_enter) * r.dir.length());
+                Color::new(transmittance, transmittance, transmittance)
+            }
+            None => Color::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+// Collision with nothing, the ray goes on weighted by the share of null extinction of each channel
+struct NullCollision {
+    weight: Color,
+}
+
+impl ScatteringFunction for NullCollision {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        *attenuation = self.weight;
+        *scattered = Ray::new(rec.p, r_in.dir);
+        true
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+}
+
+fn max_component(c: &Color) -> f64 {
+    f64::max(c.x, f64::max(c.y, c.z))
+}
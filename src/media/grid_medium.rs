@@ -0,0 +1,263 @@
+use std::sync::Arc;
+
+use crate::primitives::*;
+use crate::hittable::{Hittable, HitRecord};
+use crate::materials::{Material, Emission, ScatteringFunction, Specular, Refractive, Isotropic, HenyeyGreenstein};
+use crate::media::voxel_grid::{VoxelGrid, BRICK};
+use crate::bvh::AABBox;
+use crate::utils::random_double;
+
+// Participating medium filling an axis aligned box, with its density given by a voxel grid.
+// Collisions are found with delta tracking and transmittance is estimated with ratio tracking:
+// both sample tentative collisions with a majorant of the extinction and reject the ones landing
+// where the medium is thinner. The majorant is bounded separately inside every brick of the grid,
+// so the empty and thin regions are crossed in a few steps.
+//
+// Extinction is the same in every channel, the albedo colors the scattered light. A temperature
+// grid optionally makes the medium glow as a black body where it absorbs.
+pub struct GridMedium {
+    density: VoxelGrid,
+    temperature: Option<Temperature>,
+    min: Point3,
+    extent: Vec3,
+    sigma_t: f64,                   // Extinction coefficient where the density is one
+    albedo: Color,
+    majorants: Vec<f64>,            // Largest extinction inside each brick
+    bricks: [usize; 3],
+    scattering: Arc<Material>,      // Real collisions, scattering with the phase function
+}
+
+struct Temperature {
+    grid: VoxelGrid,
+    max_temperature: f64,   // Kelvin of the voxels at one
+    intensity: f64,         // Luminance emitted at max_temperature
+}
+
+impl GridMedium {
+    // Isotropic medium spanning the box between two opposite corners
+    pub fn new(density: VoxelGrid, a: Point3, b: Point3, sigma_t: f64, albedo: Color) -> Self {
+        let phase = Isotropic::new(albedo);
+        Self::build(density, a, b, sigma_t, albedo, phase)
+    }
+
+    // Medium scattering with a Henyey-Greenstein phase function of asymmetry g
+    pub fn new_henyey_greenstein(density: VoxelGrid, a: Point3, b: Point3, sigma_t: f64, albedo: Color, g: f64) -> Self {
+        let phase = HenyeyGreenstein::new(albedo, g);
+        Self::build(density, a, b, sigma_t, albedo, phase)
+    }
+
+    fn build(density: VoxelGrid, a: Point3, b: Point3, sigma_t: f64, albedo: Color, phase: Arc<dyn ScatteringFunction>) -> Self {
+        let min = Point3::new(f64::min(a.x, b.x), f64::min(a.y, b.y), f64::min(a.z, b.z));
+        let max = Point3::new(f64::max(a.x, b.x), f64::max(a.y, b.y), f64::max(a.z, b.z));
+
+        let (bx, by, bz) = density.bricks();
+        let mut majorants = Vec::with_capacity(bx * by * bz);
+        for z in 0..bz {
+            for y in 0..by {
+                for x in 0..bx {
+                    majorants.push(sigma_t * density.brick_max(x, y, z));
+                }
+            }
+        }
+
+        let scattering = Material::new(phase, Specular::new(), Refractive::new(1.0), None, 1.0, 0.0, 0.0, 0.0);
+
+        Self {
+            density,
+            temperature: None,
+            min,
+            extent: max - min,
+            sigma_t,
+            albedo,
+            majorants,
+            bricks: [bx, by, bz],
+            scattering: Arc::new(scattering),
+        }
+    }
+
+    // Make the medium emit as a black body. The grid holds the temperature relative to
+    // max_temperature (Kelvin), where the emitted luminance is intensity.
+    pub fn set_temperature(&mut self, grid: VoxelGrid, max_temperature: f64, intensity: f64) {
+        self.temperature = Some(Temperature { grid, max_temperature, intensity });
+    }
+
+    // Position of a point inside the unit cube spanned by the grids
+    fn to_grid(&self, p: &Point3) -> Point3 {
+        let d = *p - self.min;
+        Point3::new(d.x / self.extent.x, d.y / self.extent.y, d.z / self.extent.z)
+    }
+
+    // Section of the ray inside the box, clipped to ray_t
+    fn inside(&self, r: &Ray, ray_t: &Interval) -> Option<(f64, f64)> {
+        let mut t_enter = f64::max(ray_t.min, 0.0);
+        let mut t_exit = ray_t.max;
+
+        for axis in 0..3 {
+            let inv_d = 1.0 / r.dir[axis];
+            let mut t0 = (self.min[axis] - r.orig[axis]) * inv_d;
+            let mut t1 = (self.min[axis] + self.extent[axis] - r.orig[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_enter = f64::max(t_enter, t0);
+            t_exit = f64::min(t_exit, t1);
+        }
+
+        if t_enter < t_exit {
+            Some((t_enter, t_exit))
+        } else {
+            None
+        }
+    }
+
+    // Walk the bricks crossed by the ray between t_enter and t_exit, calling visit with the
+    // section of the ray inside each of them and their majorant, until it returns false
+    fn march(&self, r: &Ray, t_enter: f64, t_exit: f64, mut visit: impl FnMut(f64, f64, f64) -> bool) {
+        let entry = self.to_grid(&r.at(t_enter));
+        let (nx, ny, nz) = self.density.resolution();
+        let resolution = [nx, ny, nz];
+
+        let mut cell = [0isize; 3];
+        let mut step = [0isize; 3];
+        let mut next_t = [f64::INFINITY; 3];
+        let mut delta_t = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            let bricks = resolution[axis] as f64 / BRICK as f64;
+            let size = self.extent[axis] / bricks;
+            cell[axis] = ((entry[axis] * bricks).floor() as isize).clamp(0, self.bricks[axis] as isize - 1);
+
+            let d = r.dir[axis];
+            if d > 0.0 {
+                step[axis] = 1;
+                next_t[axis] = (self.min[axis] + (cell[axis] + 1) as f64 * size - r.orig[axis]) / d;
+                delta_t[axis] = size / d;
+            } else if d < 0.0 {
+                step[axis] = -1;
+                next_t[axis] = (self.min[axis] + cell[axis] as f64 * size - r.orig[axis]) / d;
+                delta_t[axis] = -size / d;
+            }
+        }
+
+        let mut t = t_enter;
+        loop {
+            let axis = if next_t[0] < next_t[1] && next_t[0] < next_t[2] {
+                0
+            } else if next_t[1] < next_t[2] {
+                1
+            } else {
+                2
+            };
+            let t_end = f64::min(next_t[axis], t_exit);
+
+            let index = cell[0] as usize + self.bricks[0] * (cell[1] as usize + self.bricks[1] * cell[2] as usize);
+            if !visit(t, t_end, self.majorants[index]) || t_end >= t_exit {
+                return;
+            }
+
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] >= self.bricks[axis] as isize {
+                return;
+            }
+            t = t_end;
+            next_t[axis] += delta_t[axis];
+        }
+    }
+
+    // Extinction at a point inside the box
+    fn extinction(&self, p: &Point3) -> f64 {
+        self.sigma_t * self.density.value(&self.to_grid(p))
+    }
+
+    // Radiance emitted where the medium absorbs, following its temperature
+    fn emission(&self, p: &Point3) -> Option<Color> {
+        let temperature = self.temperature.as_ref()?;
+        let relative = temperature.grid.value(&self.to_grid(p));
+        if relative <= 0.0 {
+            return None;
+        }
+
+        let absorbed = Color::new(1.0 - self.albedo.x, 1.0 - self.albedo.y, 1.0 - self.albedo.z);
+        let radiance = blackbody(relative * temperature.max_temperature) * temperature.intensity * relative.powi(4);
+        Some(absorbed * radiance)
+    }
+}
+
+impl Hittable for GridMedium {
+    // Delta tracking
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (t_enter, t_exit) = self.inside(r, ray_t)?;
+        let ray_length = r.dir.length();
+
+        let mut collision = None;
+        self.march(r, t_enter, t_exit, |t_start, t_end, majorant| {
+            if majorant <= 0.0 {
+                return true;
+            }
+
+            let mut t = t_start;
+            loop {
+                t += -f64::ln(1.0 - random_double()) / (majorant * ray_length);
+                if t >= t_end {
+                    return true;
+                }
+                if random_double() * majorant < self.extinction(&r.at(t)) {
+                    collision = Some(t);
+                    return false;
+                }
+            }
+        });
+
+        let t = collision?;
+        let p = r.at(t);
+        let mat = match self.emission(&p) {
+            Some(emit) => {
+                let mut emissive = (*self.scattering).clone();
+                emissive.emit = Some(Emission::new(emit));
+                Arc::new(emissive)
+            }
+            None => self.scattering.clone(),
+        };
+
+        Some(HitRecord::new(p, Vec3::new(0.0, 0.0, 0.0), mat, t, 0.0, 0.0, true))
+    }
+
+    fn bounding_box(&self) -> AABBox {
+        AABBox::new_from_points(&self.min, &(self.min + self.extent))
+    }
+
+    // Ratio tracking
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> Color {
+        let (t_enter, t_exit) = match self.inside(r, ray_t) {
+            Some(section) => section,
+            None => return Color::new(1.0, 1.0, 1.0),
+        };
+        let ray_length = r.dir.length();
+
+        let mut transmittance = 1.0;
+        self.march(r, t_enter, t_exit, |t_start, t_end, majorant| {
+            if majorant <= 0.0 {
+                return true;
+            }
+
+            let mut t = t_start;
+            loop {
+                t += -f64::ln(1.0 - random_double()) / (majorant * ray_length);
+                if t >= t_end {
+                    return true;
+                }
+                transmittance *= 1.0 - self.extinction(&r.at(t)) / majorant;
+
+                // Russian roulette once little light is left
+                if transmittance < 0.1 {
+                    if random_double() < 0.5 {
+                        transmittance = 0.0;
+                        return false;
+                    }
+                    transmittance *= 2.0;
+                }
+            }
+        });
+
+        Color::new(transmittance, transmittance, transmittance)
+    }
+}
@@ -0,0 +1,8 @@
+pub mod constant_medium;
+pub use self::constant_medium::ConstantMedium;
+
+pub mod voxel_grid;
+pub use self::voxel_grid::VoxelGrid;
+
+pub mod grid_medium;
+pub use self::grid_medium::GridMedium;
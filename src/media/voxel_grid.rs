@@ -0,0 +1,158 @@
+use crate::primitives::*;
+
+// Side, in voxels, of the bricks the grids are split into. Sparse grids only store the bricks
+// holding some value and media bound their density brick by brick.
+pub const BRICK: usize = 8;
+
+enum Storage {
+    Dense(Vec<f32>),
+    Sparse(Vec<Option<Box<[f32]>>>),    // One entry per brick, None when all its voxels are zero
+}
+
+// Scalar values (density, temperature...) sampled at the centers of the voxels of a box
+pub struct VoxelGrid {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    bricks: [usize; 3],     // Number of bricks along each axis
+    storage: Storage,
+}
+
+impl VoxelGrid {
+    // Grid keeping every voxel. Values are ordered with x varying fastest, then y, then z.
+    pub fn new(nx: usize, ny: usize, nz: usize, values: Vec<f32>) -> Self {
+        assert_eq!(values.len(), nx * ny * nz, "Voxel grid size does not match its resolution");
+
+        Self { nx, ny, nz, bricks: brick_count(nx, ny, nz), storage: Storage::Dense(values) }
+    }
+
+    // Grid keeping only the bricks with some value different from zero
+    pub fn new_sparse(nx: usize, ny: usize, nz: usize, values: Vec<f32>) -> Self {
+        assert_eq!(values.len(), nx * ny * nz, "Voxel grid size does not match its resolution");
+
+        let bricks = brick_count(nx, ny, nz);
+        let mut stored = Vec::with_capacity(bricks[0] * bricks[1] * bricks[2]);
+        for bz in 0..bricks[2] {
+            for by in 0..bricks[1] {
+                for bx in 0..bricks[0] {
+                    let mut brick = vec![0.0f32; BRICK * BRICK * BRICK];
+                    let mut empty = true;
+                    for z in 0..BRICK {
+                        for y in 0..BRICK {
+                            for x in 0..BRICK {
+                                let (gx, gy, gz) = (bx * BRICK + x, by * BRICK + y, bz * BRICK + z);
+                                if gx < nx && gy < ny && gz < nz {
+                                    let value = values[gx + nx * (gy + ny * gz)];
+                                    brick[x + BRICK * (y + BRICK * z)] = value;
+                                    empty &= value == 0.0;
+                                }
+                            }
+                        }
+                    }
+                    stored.push(if empty { None } else { Some(brick.into_boxed_slice()) });
+                }
+            }
+        }
+
+        Self { nx, ny, nz, bricks, storage: Storage::Sparse(stored) }
+    }
+
+    pub fn resolution(&self) -> (usize, usize, usize) {
+        (self.nx, self.ny, self.nz)
+    }
+
+    pub fn bricks(&self) -> (usize, usize, usize) {
+        (self.bricks[0], self.bricks[1], self.bricks[2])
+    }
+
+    // Value of a voxel, zero outside the grid
+    pub fn voxel(&self, x: isize, y: isize, z: isize) -> f64 {
+        if x < 0 || y < 0 || z < 0 || x as usize >= self.nx || y as usize >= self.ny || z as usize >= self.nz {
+            return 0.0;
+        }
+        let (x, y, z) = (x as usize, y as usize, z as usize);
+
+        match &self.storage {
+            Storage::Dense(values) => values[x + self.nx * (y + self.ny * z)] as f64,
+            Storage::Sparse(bricks) => {
+                let brick = (x / BRICK) + self.bricks[0] * ((y / BRICK) + self.bricks[1] * (z / BRICK));
+                match &bricks[brick] {
+                    Some(values) => values[x % BRICK + BRICK * (y % BRICK + BRICK * (z % BRICK))] as f64,
+                    None => 0.0,
+                }
+            }
+        }
+    }
+
+    // Trilinear interpolation of the voxels at a point of the unit cube spanned by the grid
+    pub fn value(&self, p: &Point3) -> f64 {
+        let u = p.x * self.nx as f64 - 0.5;
+        let v = p.y * self.ny as f64 - 0.5;
+        let w = p.z * self.nz as f64 - 0.5;
+        let (x, y, z) = (u.floor(), v.floor(), w.floor());
+        let (fx, fy, fz) = (u - x, v - y, w - z);
+        let (x, y, z) = (x as isize, y as isize, z as isize);
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let row = |y: isize, z: isize| lerp(self.voxel(x, y, z), self.voxel(x + 1, y, z), fx);
+        let slice = |z: isize| lerp(row(y, z), row(y + 1, z), fy);
+        lerp(slice(z), slice(z + 1), fz)
+    }
+
+    // Largest value interpolated anywhere inside a brick, which also depends on the voxels
+    // bordering it
+    pub fn brick_max(&self, bx: usize, by: usize, bz: usize) -> f64 {
+        let mut max: f64 = 0.0;
+        for z in (bz * BRICK) as isize - 1..=((bz + 1) * BRICK) as isize {
+            for y in (by * BRICK) as isize - 1..=((by + 1) * BRICK) as isize {
+                for x in (bx * BRICK) as isize - 1..=((bx + 1) * BRICK) as isize {
+                    max = f64::max(max, self.voxel(x, y, z));
+                }
+            }
+        }
+        max
+    }
+}
+
+fn brick_count(nx: usize, ny: usize, nz: usize) -> [usize; 3] {
+    [nx.div_ceil(BRICK), ny.div_ceil(BRICK), nz.div_ceil(BRICK)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random_double;
+
+    #[test]
+    fn sparse_and_dense_grids_agree() {
+        // Not a multiple of the brick size, with whole bricks left empty
+        let (nx, ny, nz) = (19, 11, 26);
+        let values: Vec<f32> = (0..nx * ny * nz)
+            .map(|i| {
+                let z = i / (nx * ny);
+                if z < 10 || random_double() < 0.3 { 0.0 } else { random_double() as f32 }
+            })
+            .collect();
+        let dense = VoxelGrid::new(nx, ny, nz, values.clone());
+        let sparse = VoxelGrid::new_sparse(nx, ny, nz, values);
+        assert_eq!(dense.bricks(), sparse.bricks());
+
+        for z in -1..=nz as isize {
+            for y in -1..=ny as isize {
+                for x in -1..=nx as isize {
+                    assert_eq!(dense.voxel(x, y, z), sparse.voxel(x, y, z));
+                }
+            }
+        }
+        for _ in 0..1000 {
+            let p = Point3::new(random_double(), random_double(), random_double());
+            assert_eq!(dense.value(&p), sparse.value(&p));
+        }
+        for (bx, by, bz) in [(0, 0, 0), (2, 1, 3), (1, 0, 2)] {
+            assert_eq!(dense.brick_max(bx, by, bz), sparse.brick_max(bx, by, bz));
+        }
+        if let Storage::Sparse(bricks) = &sparse.storage {
+            assert!(bricks.iter().any(|brick| brick.is_none()));
+        }
+    }
+}
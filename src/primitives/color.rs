@@ -2,6 +2,7 @@ use std::io::{self, Write};
 use crate::primitives::vec3::Vec3;
 use crate::primitives::interval::Interval;
 use crate::utils::random_double;
+use crate::spectrum::{cie_1931, planck, xyz_to_linear_srgb, LAMBDA_MIN, LAMBDA_MAX};
 
 pub type Color = Vec3;
 
@@ -35,4 +36,26 @@ pub fn write_color<W: Write>(out: &mut W, pixel_color: &Color) -> io::Result<()>
 
 pub fn random() -> Color {
     Color::new(random_double(), random_double(), random_double())
-}
\ No newline at end of file
+}
+
+// Relative luminance of a linear color (Rec. 709 weights)
+pub fn luminance(c: &Color) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+// Linear color of a black body at the given temperature (Kelvin), scaled to unit luminance.
+// Planck's law is integrated over the visible range against the CIE 1931 observer.
+pub fn blackbody(kelvin: f64) -> Color {
+    let mut xyz = Vec3::new(0.0, 0.0, 0.0);
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+        xyz = xyz + cie_1931(lambda) * planck(lambda, kelvin);
+        lambda += 5.0;
+    }
+    if xyz.y <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let rgb = xyz_to_linear_srgb(&(xyz / xyz.y));
+    Color::new(f64::max(rgb.x, 0.0), f64::max(rgb.y, 0.0), f64::max(rgb.z, 0.0))
+}
@@ -4,6 +4,7 @@ use crate::materials::Material;
 use crate::hittable::{Hittable, HitRecord, HittableList};
 use crate::primitives::*;
 use crate::bvh::AABBox;
+use crate::utils::{random_double, INFINITY};
 
 const EPSILON: f64 = 0.00001;
 
@@ -15,6 +16,7 @@ pub struct Quad {
     pub bbox: AABBox,         // Axis-aligned bounding box
     pub normal: Vec3,         // Surface normal
     pub offset: f64,          // Offset from the origin along the normal
+    pub area: f64,
 }
 
 impl Quad {
@@ -25,6 +27,7 @@ impl Quad {
 
         let normal = edge1.cross(&edge2).unit_vector();
         let offset = normal.dot(&corner);
+        let area = edge1.cross(&edge2).length();
 
         Quad {
             corner,
@@ -34,6 +37,7 @@ impl Quad {
             bbox,
             normal,
             offset,
+            area,
         }
     }
 
@@ -54,7 +58,7 @@ impl Quad {
         sides.add(Arc::new(Quad::new(Point3::new(max.x, min.y, min.z), -dx, dy, mat.clone())));  // back
         sides.add(Arc::new(Quad::new(Point3::new(min.x, min.y, min.z), dz, dy, mat.clone())));   // left
         sides.add(Arc::new(Quad::new(Point3::new(min.x, max.y, max.z), dx, -dz, mat.clone())));  // top
-        sides.add(Arc::new(Quad::new(Point3::new(min.x, min.y, max.z), dx, dz, mat.clone())));   // bottom
+        sides.add(Arc::new(Quad::new(Point3::new(min.x, min.y, min.z), dx, dz, mat.clone())));   // bottom
 
         sides
     }
@@ -105,6 +109,40 @@ impl Hittable for Quad {
     fn bounding_box(&self) -> AABBox {
         self.bbox
     }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.emit.is_some()
+    }
+
+    // Uniform sampling over the area converted to a solid angle density
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let rec = match self.hit(&Ray::new(*origin, *direction), &mut Interval::new(0.001, INFINITY)) {
+            Some(rec) => rec,
+            None => return 0.0,
+        };
+
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = f64::abs(direction.dot(&rec.normal) / direction.length());
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let p = self.corner + (random_double() * self.edge1) + (random_double() * self.edge2);
+        p - *origin
+    }
+
+    fn area(&self) -> f64 {
+        self.area
+    }
+
+    fn random_point(&self) -> Option<HitRecord> {
+        let u = random_double();
+        let v = random_double();
+        let p = self.corner + (u * self.edge1) + (v * self.edge2);
+
+        Some(HitRecord::new(p, self.normal, self.mat.clone(), 0.0, u, v, true))
+    }
 }
 
 fn is_interior(a: f64, b: f64, u: &mut f64, v: &mut f64) -> bool {
@@ -5,6 +5,7 @@ use crate::hittable::{Hittable, HitRecord};
 use crate::materials::Material;
 use crate::primitives::*;
 use crate::bvh::AABBox;
+use crate::utils::{random_double, INFINITY};
 
 #[derive(Clone)]
 pub struct Sphere {
@@ -93,4 +94,62 @@ impl Hittable for Sphere {
     fn bounding_box(&self) -> AABBox {
         self.bbox
     }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.emit.is_some()
+    }
+
+    // Uniform sampling of the cone of directions subtended by the sphere
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        if self.hit(&Ray::new(*origin, *direction), &mut Interval::new(0.001, INFINITY)).is_none() {
+            return 0.0;
+        }
+
+        let distance_squared = (self.center - *origin).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            // From the inside every direction reaches the sphere
+            return 1.0 / (4.0 * PI);
+        }
+
+        let cos_theta_max = f64::sqrt(1.0 - self.radius * self.radius / distance_squared);
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let direction = self.center - *origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return Vec3::random_unit_vector();
+        }
+
+        // Orthonormal basis around the direction towards the center
+        let w = direction.unit_vector();
+        let a = if f64::abs(w.x) > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let v = w.cross(&a).unit_vector();
+        let u = w.cross(&v);
+
+        let r1 = random_double();
+        let r2 = random_double();
+        let z = 1.0 + r2 * (f64::sqrt(1.0 - self.radius * self.radius / distance_squared) - 1.0);
+
+        let phi = 2.0 * PI * r1;
+        let x = f64::cos(phi) * f64::sqrt(1.0 - z * z);
+        let y = f64::sin(phi) * f64::sqrt(1.0 - z * z);
+
+        x * u + y * v + z * w
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn random_point(&self) -> Option<HitRecord> {
+        let outward_normal = Vec3::random_unit_vector();
+        let (u, v) = Sphere::get_uv(&outward_normal);
+        let p = self.center + self.radius * outward_normal;
+
+        Some(HitRecord::new(p, outward_normal, self.mat.clone(), 0.0, u, v, true))
+    }
 }
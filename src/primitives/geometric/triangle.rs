@@ -4,6 +4,7 @@ use crate::materials::Material;
 use crate::hittable::{Hittable, HitRecord};
 use crate::primitives::*;
 use crate::bvh::AABBox;
+use crate::utils::{random_double, INFINITY};
 
 const EPSILON: f64 = 0.00001;
 pub struct Triangle {
@@ -14,6 +15,7 @@ pub struct Triangle {
     pub bbox: AABBox,         // Axis-aligned bounding box
     pub normal: Vec3,         // Surface normal
     pub offset: f64,          // Offset from the origin along the normal
+    pub area: f64,
 }
 
 impl Triangle {
@@ -24,6 +26,7 @@ impl Triangle {
 
         let normal = edge1.cross(&edge2).unit_vector();
         let offset = normal.dot(&corner);
+        let area = 0.5 * edge1.cross(&edge2).length();
 
         Triangle {
             corner,
@@ -33,6 +36,7 @@ impl Triangle {
             bbox,
             normal,
             offset,
+            area,
         }
     }
 }
@@ -79,6 +83,52 @@ impl Hittable for Triangle {
     fn bounding_box(&self) -> AABBox {
         self.bbox
     }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.emit.is_some()
+    }
+
+    // Uniform sampling over the area converted to a solid angle density
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let rec = match self.hit(&Ray::new(*origin, *direction), &mut Interval::new(0.001, INFINITY)) {
+            Some(rec) => rec,
+            None => return 0.0,
+        };
+
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = f64::abs(direction.dot(&rec.normal) / direction.length());
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        // Fold the samples falling outside the triangle back into it
+        let mut a = random_double();
+        let mut b = random_double();
+        if a + b > 1.0 {
+            a = 1.0 - a;
+            b = 1.0 - b;
+        }
+
+        let p = self.corner + (a * self.edge1) + (b * self.edge2);
+        p - *origin
+    }
+
+    fn area(&self) -> f64 {
+        self.area
+    }
+
+    fn random_point(&self) -> Option<HitRecord> {
+        let mut u = random_double();
+        let mut v = random_double();
+        if u + v > 1.0 {
+            u = 1.0 - u;
+            v = 1.0 - v;
+        }
+        let p = self.corner + (u * self.edge1) + (v * self.edge2);
+
+        Some(HitRecord::new(p, self.normal, self.mat.clone(), 0.0, u, v, true))
+    }
 }
 
 fn is_interior_triangle(a: f64, b: f64, u: &mut f64, v: &mut f64) -> bool{
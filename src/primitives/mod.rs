@@ -3,7 +3,7 @@ pub mod ray;
 pub use self::ray::Ray;
 
 pub mod color;
-pub use self::color::{Color, random};
+pub use self::color::{Color, random, luminance, blackbody};
 
 pub mod vec3;
 pub use self::vec3::{Point3, Vec3, dot, cross, reflect, refract};
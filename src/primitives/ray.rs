@@ -1,15 +1,17 @@
 use crate::primitives::vec3::Vec3;
+use crate::spectrum::Wavelengths;
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Ray {
     pub orig: Vec3,
     pub dir: Vec3,
+    pub wavelengths: Wavelengths,   // What the colors gathered along the ray hold
 }
 
 impl Ray {
     // Public constructor to create a new instance of the ray
     pub fn new(orig: Vec3, dir: Vec3) -> Self {
-        Self { orig, dir }
+        Self { orig, dir, wavelengths: Wavelengths::Rgb }
     }
 
     // Public method to obtain de origin point of the ray
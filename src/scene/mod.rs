@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use crate::hittable::{Hittable, HitRecord, HittableList};
+use crate::backgrounds::Background;
+use crate::lights::{Light, AreaLight};
+use crate::materials::{Emission, LightPower};
+use crate::primitives::*;
+use crate::utils::{random_double, random_integer_range};
+
+// Everything an integrator needs to know about what is being rendered
+pub struct Scene<'a> {
+    pub world: &'a dyn Hittable,
+    pub lights: Vec<Arc<dyn Light>>,    // Lights given with the scene and emissive primitives of the world
+    pub background: Arc<dyn Background>,
+    pub spectral: bool,         // Camera rays carry sampled wavelengths instead of RGB
+    emitter_cdf: Vec<f64>,      // Accumulated probability of each light starting light subpaths
+    light_point_pdf: f64,       // Area pdf of a point of the area lights starting a light subpath
+}
+
+impl<'a> Scene<'a> {
+    pub fn new(world: &'a dyn Hittable, lights: &[Arc<dyn Light>], background: Arc<dyn Background>) -> Self {
+        let mut lights = lights.to_vec();
+        let mut emissive = HittableList::new();
+        world.collect_lights(&mut emissive);
+        calibrate_emissions(&emissive);
+        for object in emissive.objects {
+            lights.push(Arc::new(AreaLight::new(object)));
+        }
+
+        // Light subpaths start uniformly on the analytic lights and on the area lights as a whole,
+        // where the point is uniformly distributed over their total area
+        let light_area: f64 = lights.iter().filter(|light| light.is_area()).map(|light| light.area()).sum();
+        let analytic = lights.iter().filter(|light| !light.is_area() && !light.is_infinite()).count();
+        let groups = analytic + if light_area > 0.0 { 1 } else { 0 };
+
+        let mut emitter_cdf = Vec::with_capacity(lights.len());
+        let mut total = 0.0;
+        for light in &lights {
+            if light.is_area() {
+                total += light.area() / (light_area * groups as f64);
+            } else if !light.is_infinite() {
+                total += 1.0 / groups as f64;
+            }
+            emitter_cdf.push(total);
+        }
+
+        let light_point_pdf = if light_area > 0.0 { 1.0 / (groups as f64 * light_area) } else { 0.0 };
+
+        Scene { world, lights, background, spectral: false, emitter_cdf, light_point_pdf }
+    }
+
+    // Light chosen uniformly for direct lighting, along with its probability
+    pub fn sample_light(&self) -> Option<(&Arc<dyn Light>, f64)> {
+        if self.lights.is_empty() {
+            return None;
+        }
+
+        let index = random_integer_range(0, self.lights.len() as i32) as usize;
+        Some((&self.lights[index], 1.0 / self.lights.len() as f64))
+    }
+
+    // Solid angle pdf of a direction from origin being sampled by direct lighting from the area lights
+    pub fn light_pdf(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+
+        let weight = 1.0 / self.lights.len() as f64;
+        self.lights
+            .iter()
+            .map(|light| weight * light.pdf_li(origin, direction))
+            .sum()
+    }
+
+    // First point of an area light hit by the ray within ray_t
+    pub fn hit_light(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let mut closest_so_far = ray_t.max;
+        let mut rec = None;
+        for light in &self.lights {
+            if let Some(light_rec) = light.hit(r, &mut Interval::new(ray_t.min, closest_so_far)) {
+                closest_so_far = light_rec.t;
+                rec = Some(light_rec);
+            }
+        }
+        rec
+    }
+
+    // Light starting a light subpath or a photon, along with its probability. Lights infinitely
+    // far away are never chosen.
+    pub fn sample_emitter(&self) -> Option<(&Arc<dyn Light>, f64)> {
+        let total = self.emitter_cdf.last().copied().unwrap_or(0.0);
+        if total <= 0.0 {
+            return None;
+        }
+
+        let target = random_double() * total;
+        let index = self
+            .emitter_cdf
+            .partition_point(|&p| p <= target)
+            .min(self.emitter_cdf.len() - 1);
+
+        let previous = if index > 0 { self.emitter_cdf[index - 1] } else { 0.0 };
+        Some((&self.lights[index], self.emitter_cdf[index] - previous))
+    }
+
+    // Area pdf of a point of the area lights starting a light subpath, the same for all of them
+    // (probability of the light times the area pdf of the point on it)
+    pub fn light_point_pdf(&self) -> f64 {
+        self.light_point_pdf
+    }
+}
+
+// Emissions given as a power spread it over all the primitives using them. Their total area and
+// the average luminance of their texture over it are estimated from points sampled on them.
+fn calibrate_emissions(emissive: &HittableList) {
+    const SAMPLES: f64 = 4096.0;
+
+    // Primitives grouped by the emission they use, with their total area
+    let mut groups: Vec<(Arc<Emission>, Vec<usize>, f64)> = Vec::new();
+    for (index, object) in emissive.objects.iter().enumerate() {
+        let emission = match object.random_point().and_then(|rec| rec.mat.emit.clone()) {
+            Some(emission) if !matches!(emission.power(), LightPower::Radiance(_)) => emission,
+            _ => continue,
+        };
+        match groups.iter_mut().find(|(other, _, _)| Arc::ptr_eq(other, &emission)) {
+            Some((_, objects, area)) => {
+                objects.push(index);
+                *area += object.area();
+            }
+            None => groups.push((emission, vec![index], object.area())),
+        }
+    }
+
+    for (emission, objects, area) in groups {
+        if area <= 0.0 {
+            continue;
+        }
+
+        let mut luminance_area = 0.0;
+        for object in objects.iter().map(|&index| &emissive.objects[index]) {
+            let samples = f64::ceil(SAMPLES * object.area() / area) as usize;
+            let mut sum = 0.0;
+            for _ in 0..samples {
+                if let Some(rec) = object.random_point() {
+                    sum += emission.texture_luminance(&rec);
+                }
+            }
+            luminance_area += sum / samples as f64 * object.area();
+        }
+        emission.set_emitting_area(area, luminance_area / area);
+    }
+}
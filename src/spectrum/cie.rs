@@ -0,0 +1,53 @@
+use crate::primitives::{Vec3, Color};
+
+// Visible range covered by the color matching functions, in nanometers
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+
+// CIE 1931 color matching functions, multi-lobe gaussian fit by Wyman, Sloan and Shirley
+pub fn cie_1931(lambda: f64) -> Vec3 {
+    let g = |mu: f64, sigma1: f64, sigma2: f64| {
+        let t = (lambda - mu) / if lambda < mu { sigma1 } else { sigma2 };
+        f64::exp(-0.5 * t * t)
+    };
+
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+// Spectral radiance of a black body at a wavelength in nanometers (arbitrary units)
+pub fn planck(lambda_nm: f64, kelvin: f64) -> f64 {
+    let c1 = 3.741771852e-16;
+    let c2 = 1.438776877e-2;
+    let lambda = lambda_nm * 1e-9;
+    c1 / (lambda.powi(5) * (f64::exp(c2 / (lambda * kelvin)) - 1.0))
+}
+
+pub fn xyz_to_linear_srgb(xyz: &Vec3) -> Color {
+    Color::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+// RGB color of a reflectance spectrum, integrated every 10 nm against the color matching
+// functions. Balanced so that a constant reflectance of one gives white.
+pub fn reflectance_to_rgb(reflectance: impl Fn(f64) -> f64) -> Color {
+    let mut xyz = Vec3::new(0.0, 0.0, 0.0);
+    let mut white = Vec3::new(0.0, 0.0, 0.0);
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+        let cie = cie_1931(lambda);
+        xyz = xyz + cie * reflectance(lambda);
+        white = white + cie;
+        lambda += 10.0;
+    }
+
+    let rgb = xyz_to_linear_srgb(&(xyz / white.y));
+    let white = xyz_to_linear_srgb(&(white / white.y));
+    Color::new(f64::max(rgb.x / white.x, 0.0), f64::max(rgb.y / white.y, 0.0), f64::max(rgb.z / white.z, 0.0))
+}
@@ -0,0 +1,111 @@
+use crate::primitives::Color;
+
+// Complex index of refraction of a conductor, eta + i k, at the red, green and blue wavelengths
+#[derive(Debug, Clone, Copy)]
+pub struct ComplexIor {
+    pub eta: Color,
+    pub k: Color,
+}
+
+impl ComplexIor {
+    // Measured data fitted to the sRGB primaries
+    pub const GOLD: ComplexIor = ComplexIor {
+        eta: Color { x: 0.143119, y: 0.374957, z: 1.442479 },
+        k: Color { x: 3.983160, y: 2.385721, z: 1.603215 },
+    };
+    pub const SILVER: ComplexIor = ComplexIor {
+        eta: Color { x: 0.155265, y: 0.116723, z: 0.138342 },
+        k: Color { x: 4.828181, y: 3.122249, z: 2.146961 },
+    };
+    pub const COPPER: ComplexIor = ComplexIor {
+        eta: Color { x: 0.200438, y: 0.924033, z: 1.102212 },
+        k: Color { x: 3.912949, y: 2.452848, z: 2.142188 },
+    };
+    pub const ALUMINIUM: ComplexIor = ComplexIor {
+        eta: Color { x: 1.657460, y: 0.880369, z: 0.521229 },
+        k: Color { x: 9.223869, y: 6.269523, z: 4.837001 },
+    };
+    pub const CHROME: ComplexIor = ComplexIor {
+        eta: Color { x: 4.369683, y: 2.916703, z: 1.654701 },
+        k: Color { x: 5.206434, y: 4.231365, z: 3.754947 },
+    };
+
+    pub fn new(eta: Color, k: Color) -> Self {
+        Self { eta, k }
+    }
+
+    // Index at a wavelength in nanometers, interpolated between the ones of the blue, green and
+    // red primaries
+    pub fn at(&self, lambda: f64) -> (f64, f64) {
+        const PRIMARIES: [f64; 3] = [465.0, 550.0, 610.0];
+        let eta = [self.eta.z, self.eta.y, self.eta.x];
+        let k = [self.k.z, self.k.y, self.k.x];
+
+        if lambda <= PRIMARIES[0] {
+            return (eta[0], k[0]);
+        }
+        for i in 0..2 {
+            if lambda <= PRIMARIES[i + 1] {
+                let t = (lambda - PRIMARIES[i]) / (PRIMARIES[i + 1] - PRIMARIES[i]);
+                return (eta[i] + t * (eta[i + 1] - eta[i]), k[i] + t * (k[i + 1] - k[i]));
+            }
+        }
+        (eta[2], k[2])
+    }
+
+    // Fresnel reflectance of unpolarized light arriving at an angle with the given cosine
+    pub fn reflectance(&self, cos_theta: f64) -> Color {
+        Color::new(
+            fresnel_conductor(cos_theta, self.eta.x, self.k.x),
+            fresnel_conductor(cos_theta, self.eta.y, self.k.y),
+            fresnel_conductor(cos_theta, self.eta.z, self.k.z),
+        )
+    }
+}
+
+// Exact Fresnel equations for a conductor, averaging the s and p polarizations
+fn fresnel_conductor(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = f64::clamp(cos_theta * cos_theta, 0.0, 1.0);
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = f64::sqrt(t0 * t0 + 4.0 * eta2 * k2);
+    let t1 = a2_plus_b2 + cos2;
+    let a = f64::sqrt(f64::max(0.5 * (a2_plus_b2 + t0), 0.0));
+    let t2 = 2.0 * f64::sqrt(cos2) * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::scattering_function::microfacet::fresnel_dielectric;
+
+    #[test]
+    fn conductor_without_absorption_is_a_dielectric() {
+        for eta in [1.33, 1.5, 2.4] {
+            for i in 0..=20 {
+                let cos_theta = i as f64 / 20.0;
+                let conductor = fresnel_conductor(cos_theta, eta, 0.0);
+                let dielectric = fresnel_dielectric(cos_theta, eta);
+                assert!((conductor - dielectric).abs() < 1e-9, "eta {eta} cos {cos_theta}: {conductor} != {dielectric}");
+            }
+        }
+    }
+
+    #[test]
+    fn conductors_reflect_everything_at_grazing_angles() {
+        for ior in [ComplexIor::GOLD, ComplexIor::SILVER, ComplexIor::COPPER, ComplexIor::ALUMINIUM, ComplexIor::CHROME] {
+            let grazing = ior.reflectance(0.0);
+            assert!((grazing.x - 1.0).abs() < 1e-9 && (grazing.y - 1.0).abs() < 1e-9 && (grazing.z - 1.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::f64::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+
+// Reflectance of unpolarized light of a wavelength in nanometers, arriving at an angle with the
+// given cosine from a medium of index outside onto a film of index film and thickness in
+// nanometers, over a substrate of complex index substrate_eta + i substrate_k. The Airy sum of the
+// light bouncing inside the film, whose waves interfere depending on the delay between them.
+pub fn thin_film_reflectance(cos_theta: f64, lambda: f64, thickness: f64, outside: f64, film: f64, substrate_eta: f64, substrate_k: f64) -> f64 {
+    // Grazing light is all reflected, the Fresnel coefficients would be 0 / 0 for a film
+    // matching the outside
+    if cos_theta <= 0.0 {
+        return 1.0;
+    }
+    let cos0 = Complex::real(f64::min(cos_theta, 1.0));
+    let n0 = Complex::real(outside);
+    let n1 = Complex::real(film);
+    let n2 = Complex::new(substrate_eta, substrate_k);
+
+    // Snell's law n0 sin0 = n1 sin1 = n2 sin2, the cosines turn complex past the critical angle
+    let sin2_0 = 1.0 - cos0.re * cos0.re;
+    let cosine = |n: Complex| (Complex::real(1.0) - Complex::real(outside * outside * sin2_0) / (n * n)).sqrt();
+    let cos1 = cosine(n1);
+    let cos2 = cosine(n2);
+
+    // Phase delay of every round trip through the film
+    let delta = Complex::real(4.0 * PI * thickness / lambda) * n1 * cos1;
+    let round_trip = (Complex::new(0.0, 1.0) * delta).exp();
+
+    let airy = |r01: Complex, r12: Complex| {
+        let r = (r01 + r12 * round_trip) / (Complex::real(1.0) + r01 * r12 * round_trip);
+        r.norm_sqr()
+    };
+    let s = airy(fresnel_s(n0, cos0, n1, cos1), fresnel_s(n1, cos1, n2, cos2));
+    let p = airy(fresnel_p(n0, cos0, n1, cos1), fresnel_p(n1, cos1, n2, cos2));
+    (0.5 * (s + p)).clamp(0.0, 1.0)
+}
+
+// Fresnel amplitude reflection coefficients for the s and p polarizations
+fn fresnel_s(ni: Complex, cos_i: Complex, nt: Complex, cos_t: Complex) -> Complex {
+    (ni * cos_i - nt * cos_t) / (ni * cos_i + nt * cos_t)
+}
+
+fn fresnel_p(ni: Complex, cos_i: Complex, nt: Complex, cos_t: Complex) -> Complex {
+    (nt * cos_i - ni * cos_t) / (nt * cos_i + ni * cos_t)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn real(re: f64) -> Self {
+        Self { re, im: 0.0 }
+    }
+
+    fn norm_sqr(&self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    // Principal square root, with a non negative imaginary part for negative reals so that
+    // evanescent waves decay
+    fn sqrt(&self) -> Self {
+        let norm = self.norm_sqr().sqrt();
+        let re = f64::sqrt(f64::max(0.5 * (norm + self.re), 0.0));
+        let im = f64::sqrt(f64::max(0.5 * (norm - self.re), 0.0));
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    fn exp(&self) -> Self {
+        let scale = self.re.exp();
+        Self::new(scale * self.im.cos(), scale * self.im.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(self.re * other.re - self.im * other.im, self.re * other.im + self.im * other.re)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        let denom = other.norm_sqr();
+        Self::new(
+            (self.re * other.re + self.im * other.im) / denom,
+            (self.im * other.re - self.re * other.im) / denom,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::scattering_function::microfacet::fresnel_dielectric;
+    use crate::spectrum::ComplexIor;
+
+    #[test]
+    fn film_without_thickness_is_the_bare_interface() {
+        let (gold_eta, gold_k) = ComplexIor::GOLD.at(610.0);
+        for i in 0..=20 {
+            let cos_theta = i as f64 / 20.0;
+            let glass = thin_film_reflectance(cos_theta, 550.0, 0.0, 1.0, 1.33, 1.5, 0.0);
+            let bare_glass = fresnel_dielectric(cos_theta, 1.5);
+            assert!((glass - bare_glass).abs() < 1e-9, "glass cos {cos_theta}: {glass} != {bare_glass}");
+
+            let gold = thin_film_reflectance(cos_theta, 610.0, 0.0, 1.0, 1.5, gold_eta, gold_k);
+            let bare_gold = ComplexIor::GOLD.reflectance(cos_theta).x;
+            assert!((gold - bare_gold).abs() < 1e-9, "gold cos {cos_theta}: {gold} != {bare_gold}");
+        }
+    }
+
+    #[test]
+    fn film_matching_the_outside_is_invisible() {
+        for thickness in [100.0, 350.0, 1000.0] {
+            for i in 0..=20 {
+                let cos_theta = i as f64 / 20.0;
+                let coated = thin_film_reflectance(cos_theta, 500.0, thickness, 1.0, 1.0, 1.5, 0.0);
+                let bare = fresnel_dielectric(cos_theta, 1.5);
+                assert!((coated - bare).abs() < 1e-9, "thickness {thickness} cos {cos_theta}: {coated} != {bare}");
+            }
+        }
+    }
+}
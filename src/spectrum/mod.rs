@@ -0,0 +1,14 @@
+pub mod cie;
+pub use self::cie::{cie_1931, planck, xyz_to_linear_srgb, reflectance_to_rgb, LAMBDA_MIN, LAMBDA_MAX};
+
+pub mod wavelengths;
+pub use self::wavelengths::Wavelengths;
+
+pub mod refractive_index;
+pub use self::refractive_index::RefractiveIndex;
+
+pub mod complex_ior;
+pub use self::complex_ior::ComplexIor;
+
+pub mod interference;
+pub use self::interference::thin_film_reflectance;
@@ -0,0 +1,58 @@
+use crate::spectrum::Wavelengths;
+
+// Wavelength at which the index of paths carrying RGB is taken, the sodium d line
+const D_LINE: f64 = 587.6;
+
+// Index of refraction of a dielectric as a function of the wavelength
+#[derive(Debug, Clone, Copy)]
+pub enum RefractiveIndex {
+    Constant(f64),
+    // n = a + b / λ², with λ in micrometers
+    Cauchy { a: f64, b: f64 },
+    // n² = 1 + Σ b λ² / (λ² - c), with λ in micrometers
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl RefractiveIndex {
+    pub const BK7: RefractiveIndex = RefractiveIndex::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    pub const FUSED_SILICA: RefractiveIndex = RefractiveIndex::Sellmeier {
+        b: [0.6961663, 0.4079426, 0.8974794],
+        c: [0.00467914826, 0.0135120631, 97.9340025],
+    };
+    pub const DIAMOND: RefractiveIndex = RefractiveIndex::Sellmeier {
+        b: [4.3356, 0.3306, 0.0],
+        c: [0.011236, 0.030625, 0.0],
+    };
+
+    // Index at a wavelength in nanometers
+    pub fn at(&self, lambda_nm: f64) -> f64 {
+        let lambda = lambda_nm * 1e-3;
+        match self {
+            RefractiveIndex::Constant(n) => *n,
+            RefractiveIndex::Cauchy { a, b } => a + b / (lambda * lambda),
+            RefractiveIndex::Sellmeier { b, c } => {
+                let l2 = lambda * lambda;
+                let sum: f64 = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+                f64::sqrt(1.0 + sum)
+            }
+        }
+    }
+
+    // Index seen by a path, at its hero wavelength if it carries any
+    pub fn value(&self, wavelengths: &Wavelengths) -> f64 {
+        self.at(wavelengths.hero().unwrap_or(D_LINE))
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, RefractiveIndex::Constant(_))
+    }
+}
+
+impl Default for RefractiveIndex {
+    fn default() -> Self {
+        RefractiveIndex::Constant(1.0)
+    }
+}
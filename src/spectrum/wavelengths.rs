@@ -0,0 +1,177 @@
+use std::sync::OnceLock;
+
+use crate::primitives::{Vec3, Color};
+use crate::spectrum::cie::{cie_1931, xyz_to_linear_srgb, LAMBDA_MIN, LAMBDA_MAX};
+use crate::utils::random_double;
+
+// Wavelengths carried by a path, telling what the channels of its colors hold
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Wavelengths {
+    // Plain red, green and blue
+    #[default]
+    Rgb,
+    // Values at three wavelengths in nanometers. Once terminated only the first one, the hero
+    // wavelength, still counts.
+    Sampled { lambda: [f64; 3], terminated: bool },
+}
+
+impl Wavelengths {
+    // Hero wavelength uniformly distributed over the visible range, the other two evenly spaced
+    // after it (wrapping around the range)
+    pub fn sample() -> Self {
+        let u = random_double();
+        let lambda = [0.0, 1.0, 2.0].map(|i| LAMBDA_MIN + (u + i / 3.0).fract() * (LAMBDA_MAX - LAMBDA_MIN));
+        Wavelengths::Sampled { lambda, terminated: false }
+    }
+
+    pub fn hero(&self) -> Option<f64> {
+        match self {
+            Wavelengths::Rgb => None,
+            Wavelengths::Sampled { lambda, .. } => Some(lambda[0]),
+        }
+    }
+
+    // Drop every wavelength but the hero, once the path took a direction that depends on it
+    pub fn terminate_secondary(&mut self) {
+        if let Wavelengths::Sampled { terminated, .. } = self {
+            *terminated = true;
+        }
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        matches!(self, Wavelengths::Sampled { terminated: true, .. })
+    }
+
+    // Values at the carried wavelengths of the spectrum an RGB color is upsampled to
+    pub fn from_rgb(&self, rgb: &Color) -> Color {
+        match self {
+            Wavelengths::Rgb => *rgb,
+            Wavelengths::Sampled { lambda, .. } => {
+                let spectrum = smits(rgb);
+                Color::new(spectrum(lambda[0]), spectrum(lambda[1]), spectrum(lambda[2]))
+            }
+        }
+    }
+
+    // RGB color of the values carried, estimating their integral against the color matching
+    // functions. Balanced so that a constant spectrum of one gives white.
+    pub fn to_rgb(&self, values: &Color) -> Color {
+        match self {
+            Wavelengths::Rgb => *values,
+            Wavelengths::Sampled { lambda, terminated } => {
+                let count = if *terminated { 1 } else { 3 };
+                let mut xyz = Vec3::new(0.0, 0.0, 0.0);
+                for i in 0..count {
+                    xyz = xyz + cie_1931(lambda[i]) * values[i];
+                }
+
+                let calibration = calibration();
+                let xyz = xyz * (LAMBDA_MAX - LAMBDA_MIN) / (count as f64 * calibration.y_integral);
+                xyz_to_linear_srgb(&xyz) * calibration.white_balance
+            }
+        }
+    }
+}
+
+// Normalization of the conversion back to RGB, integrated once
+struct Calibration {
+    y_integral: f64,        // Integral of the luminance matching function
+    white_balance: Color,   // Inverse of the RGB of a constant spectrum of one
+}
+
+fn calibration() -> &'static Calibration {
+    static CALIBRATION: OnceLock<Calibration> = OnceLock::new();
+    CALIBRATION.get_or_init(|| {
+        let mut integral = Vec3::new(0.0, 0.0, 0.0);
+        let mut lambda = LAMBDA_MIN;
+        while lambda <= LAMBDA_MAX {
+            integral = integral + cie_1931(lambda);
+            lambda += 1.0;
+        }
+
+        let white = xyz_to_linear_srgb(&(integral / integral.y));
+        Calibration {
+            y_integral: integral.y,
+            white_balance: Color::new(1.0 / white.x, 1.0 / white.y, 1.0 / white.z),
+        }
+    })
+}
+
+// Smits' RGB to spectrum conversion: the color is split into white, then the secondary and the
+// primary closest to it, each with a smooth spectrum tabulated over ten bins from 380 to 720 nm
+fn smits(rgb: &Color) -> impl Fn(f64) -> f64 {
+    const WHITE: [f64; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+    const CYAN: [f64; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+    const MAGENTA: [f64; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+    const YELLOW: [f64; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+    const RED: [f64; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+    const GREEN: [f64; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+    const BLUE: [f64; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+    let (r, g, b) = (rgb.x, rgb.y, rgb.z);
+    let terms: [(f64, &[f64; 10]); 3] = if r <= g && r <= b {
+        if g <= b {
+            [(r, &WHITE), (g - r, &CYAN), (b - g, &BLUE)]
+        } else {
+            [(r, &WHITE), (b - r, &CYAN), (g - b, &GREEN)]
+        }
+    } else if g <= r && g <= b {
+        if r <= b {
+            [(g, &WHITE), (r - g, &MAGENTA), (b - r, &BLUE)]
+        } else {
+            [(g, &WHITE), (b - g, &MAGENTA), (r - b, &RED)]
+        }
+    } else if r <= g {
+        [(b, &WHITE), (r - b, &YELLOW), (g - r, &GREEN)]
+    } else {
+        [(b, &WHITE), (g - b, &YELLOW), (r - g, &RED)]
+    };
+
+    move |lambda: f64| {
+        // Linear interpolation between the bin centers, constant past the first and last ones
+        let x = ((lambda - 380.0) / 34.0 - 0.5).clamp(0.0, 9.0);
+        let i = usize::min(x as usize, 8);
+        let t = x - i as f64;
+        terms
+            .iter()
+            .map(|(weight, table)| weight * (table[i] * (1.0 - t) + table[i + 1] * t))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RGB colors given for evenly spaced hero wavelengths, averaged
+    fn average(rgb: impl Fn(&Wavelengths) -> Color) -> Color {
+        let steps = 4000;
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for step in 0..steps {
+            let u = (step as f64 + 0.5) / steps as f64;
+            let lambda = [0.0, 1.0, 2.0].map(|i| LAMBDA_MIN + (u + i / 3.0).fract() * (LAMBDA_MAX - LAMBDA_MIN));
+            total = total + rgb(&Wavelengths::Sampled { lambda, terminated: false });
+        }
+        total / steps as f64
+    }
+
+    fn assert_close(a: &Color, b: &Color, tolerance: f64) {
+        assert!((a.x - b.x).abs() < tolerance && (a.y - b.y).abs() < tolerance && (a.z - b.z).abs() < tolerance,
+            "({}, {}, {}) != ({}, {}, {})", a.x, a.y, a.z, b.x, b.y, b.z);
+    }
+
+    #[test]
+    fn constant_spectrum_is_white() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        assert_close(&average(|wavelengths| wavelengths.to_rgb(&white)), &white, 1e-3);
+    }
+
+    #[test]
+    fn white_round_trips_to_white() {
+        for value in [1.0, 0.5, 2.0] {
+            let grey = Color::new(value, value, value);
+            let round_trip = average(|wavelengths| wavelengths.to_rgb(&wavelengths.from_rgb(&grey)));
+            assert_close(&round_trip, &grey, 2e-3 * value);
+        }
+    }
+}